@@ -2,15 +2,19 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::{notify::EventKind, schedule::TimeWindow};
 use otr_utils::cutting::CutlistRating;
 
 use anyhow::{anyhow, Context};
 use log::*;
 use once_cell::sync::OnceCell;
+use regex::Regex;
 use std::{
+    collections::HashMap,
     fs::File,
-    io::BufReader,
+    io::{self, BufReader},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 /// Name of configuration file
@@ -93,7 +97,7 @@ pub fn min_cutlist_rating() -> Option<CutlistRating> {
 /// the configuration file.  In case an error occurred while reading the
 /// configuration data from the file, None is returned. Warnings are logged if
 /// either user or password is not maintained. This is done because this function
-/// is only called if this data is required
+/// is only called if videos shall be decoded
 pub fn otr_access_data() -> Option<(&'static str, &'static str)> {
     match cfg_from_file() {
         Ok(cfg) => match &cfg.decoding {
@@ -101,20 +105,17 @@ pub fn otr_access_data() -> Option<(&'static str, &'static str)> {
                 warn!("OTR access data is not maintained in configuration file");
                 None
             }
-            Some(_decoding) => {
-                if _decoding.user.is_none() {
+            Some(_decoding) => match (&_decoding.user, &_decoding.password) {
+                (None, _) => {
                     warn!("OTR user is not maintained in configuration file");
                     None
-                } else if _decoding.password.is_none() {
+                }
+                (_, None) => {
                     warn!("OTR password is not maintained in configuration file");
                     None
-                } else {
-                    Some((
-                        _decoding.user.as_ref().unwrap(),
-                        _decoding.password.as_ref().unwrap(),
-                    ))
                 }
-            }
+                (Some(_user), Some(_password)) => Some((_user, _password)),
+            },
         },
         Err(err) => {
             warn!(
@@ -149,6 +150,245 @@ pub fn submit_cutlists() -> bool {
     }
 }
 
+/// Checks if the configuration file can be read and parsed
+pub fn check() -> anyhow::Result<()> {
+    cfg_from_file().map(|_| ())
+}
+
+/// Removes the credentials (OTR user and password, access token for
+/// cutlist.at) from the configuration file. Returns true if the file contained
+/// credentials, and false otherwise (or if there is no configuration file)
+pub fn remove_credentials() -> anyhow::Result<bool> {
+    let path = file_path().ok_or_else(|| {
+        anyhow!("Could not determine path of configuration directory for this OS")
+    })?;
+    let file = match File::open(&path) {
+        Ok(_file) => _file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => {
+            return Err(anyhow!(err).context(format!(
+                "could not open configuration file \"{}\"",
+                path.display()
+            )))
+        }
+    };
+    let mut cfg: serde_json::Value = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("could not read configuration file \"{}\"", path.display()))?;
+
+    let mut is_removed = false;
+    for (section, key) in [
+        ("decoding", "user"),
+        ("decoding", "password"),
+        ("cutting", "cutlist_at_access_token"),
+    ] {
+        if let Some(_section) = cfg.get_mut(section).and_then(|value| value.as_object_mut()) {
+            is_removed |= _section.remove(key).is_some();
+        }
+    }
+
+    if is_removed {
+        let file = File::create(&path)
+            .with_context(|| format!("could not open configuration file \"{}\"", path.display()))?;
+        serde_json::to_writer_pretty(file, &cfg).with_context(|| {
+            format!("could not write configuration file \"{}\"", path.display())
+        })?;
+    }
+
+    Ok(is_removed)
+}
+
+/// Returns the path of the configuration file. None is returned if the
+/// configuration directory of the OS cannot be determined
+pub fn file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|cfg_dir| cfg_dir.join(CFG_FILENAME))
+}
+
+/// Returns the upload settings from the configuration file. In case an error
+/// occurred while reading the configuration data from the file, or no upload
+/// is configured, None is returned
+pub fn upload() -> Option<&'static Upload> {
+    match cfg_from_file() {
+        Ok(cfg) => cfg.upload.as_ref(),
+        Err(err) => {
+            trace!(
+                "Cannot determine upload settings from configuration: {:?}",
+                err
+            );
+            None
+        }
+    }
+}
+
+/// Returns the notification settings from the configuration file. In case an
+/// error occurred while reading the configuration data from the file, or no
+/// notifications are configured, an empty slice is returned
+pub fn notifications() -> &'static [Notification] {
+    match cfg_from_file() {
+        Ok(cfg) => cfg.notifications.as_deref().unwrap_or_default(),
+        Err(err) => {
+            trace!(
+                "Cannot determine notification settings from configuration: {:?}",
+                err
+            );
+            &[]
+        }
+    }
+}
+
+/// Returns the proxy settings from the configuration file as pairs of the
+/// corresponding environment variable and its value. These environment
+/// variables are evaluated by the HTTP client for all requests. In case an
+/// error occurred while reading the configuration data from the file, or no
+/// proxy is configured, an empty vector is returned
+pub fn proxy_env_vars() -> Vec<(&'static str, &'static str)> {
+    match cfg_from_file() {
+        Ok(cfg) => {
+            if let Some(_proxy) = &cfg.proxy {
+                [
+                    ("ALL_PROXY", &_proxy.all),
+                    ("HTTP_PROXY", &_proxy.http),
+                    ("HTTPS_PROXY", &_proxy.https),
+                    ("NO_PROXY", &_proxy.no_proxy),
+                ]
+                .into_iter()
+                .filter_map(|(var, value)| value.as_deref().map(|_value| (var, _value)))
+                .collect()
+            } else {
+                vec![]
+            }
+        }
+        Err(err) => {
+            trace!(
+                "Cannot determine proxy settings from configuration: {:?}",
+                err
+            );
+            vec![]
+        }
+    }
+}
+
+/// Returns the additional environment variables for the external tools (e.g.,
+/// ffmpeg) from the configuration file. In case an error occurred while reading
+/// the configuration data from the file, or no variables are configured, an
+/// empty vector is returned
+pub fn tool_env_vars() -> Vec<(&'static str, &'static str)> {
+    match cfg_from_file() {
+        Ok(cfg) => cfg
+            .tool_env
+            .iter()
+            .flatten()
+            .map(|(var, value)| (var.as_str(), value.as_str()))
+            .collect(),
+        Err(err) => {
+            trace!(
+                "Cannot determine environment variables for external tools from configuration: {:?}",
+                err
+            );
+            vec![]
+        }
+    }
+}
+
+/// Returns the time window in which videos may be cut when processing all
+/// videos. In case an error occurred while reading the configuration data from
+/// the file, or no (valid) window is configured, None is returned
+pub fn cutting_window() -> Option<TimeWindow> {
+    time_window("cutting", |schedule| schedule.cutting.as_deref())
+}
+
+/// Returns the time window in which videos may be decoded when processing all
+/// videos. In case an error occurred while reading the configuration data from
+/// the file, or no (valid) window is configured, None is returned
+pub fn decoding_window() -> Option<TimeWindow> {
+    time_window("decoding", |schedule| schedule.decoding.as_deref())
+}
+
+/// Returns true if crash reports shall be written. In case an error occurred
+/// while reading the configuration data from the file, false is returned
+pub fn crash_reports() -> bool {
+    match cfg_from_file() {
+        Ok(cfg) => cfg.crash_reports.unwrap_or(false),
+        Err(err) => {
+            trace!(
+                "Cannot determine whether crash reports shall be written: {:?}",
+                err
+            );
+            false
+        }
+    }
+}
+
+/// Returns the names of the settings that are maintained in the configuration
+/// file (without their values, which might be confidential). In case an error
+/// occurred while reading the configuration data from the file, an empty vector
+/// is returned
+pub fn configured_settings() -> Vec<&'static str> {
+    // The fields are destructured without "..", so that this function must be
+    // adjusted whenever a setting is added
+    macro_rules! configured {
+        ($cfg:expr, $($field:ident),+) => {{
+            let CfgFromFile { $($field),+ } = $cfg;
+            [$((stringify!($field), $field.is_some())),+]
+        }};
+    }
+
+    match cfg_from_file() {
+        Ok(cfg) => configured!(
+            cfg,
+            working_dir,
+            stability_window,
+            av_sync_threshold,
+            crash_reports,
+            decoding,
+            cutting,
+            schedule,
+            proxy,
+            upload,
+            tool_env,
+            notifications
+        )
+        .into_iter()
+        .filter_map(|(name, is_set)| is_set.then_some(name))
+        .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Returns the stability window from the configuration file: Encoded videos
+/// that were modified within that window are regarded as still being written.
+/// In case an error occurred while reading the configuration data from the
+/// file, or no window is configured, a window of zero seconds is returned
+pub fn stability_window() -> Duration {
+    match cfg_from_file() {
+        Ok(cfg) => Duration::from_secs(cfg.stability_window.unwrap_or(0)),
+        Err(err) => {
+            trace!(
+                "Cannot determine stability window from configuration: {:?}",
+                err
+            );
+            Duration::ZERO
+        }
+    }
+}
+
+/// Returns the threshold from the configuration file above which a constant
+/// offset between audio and video of a decoded video is corrected before it is
+/// cut. In case an error occurred while reading the configuration data from the
+/// file, or no threshold is configured, None is returned (i.e., the offset is
+/// not corrected)
+pub fn av_sync_threshold() -> Option<Duration> {
+    match cfg_from_file() {
+        Ok(cfg) => cfg.av_sync_threshold.map(Duration::from_millis),
+        Err(err) => {
+            warn!(
+                "Cannot determine A/V sync threshold from configuration: {:?}",
+                err
+            );
+            None
+        }
+    }
+}
+
 /// Returns the working directory from configuration file. In case an error
 /// occurred while reading the configuration data from the file, None is
 /// returned
@@ -165,12 +405,44 @@ pub fn working_dir() -> Option<&'static Path> {
     }
 }
 
+/// Retrieves the time window of a processing stage from the schedule section of
+/// the configuration file. Invalid time windows are ignored with a warning
+fn time_window<F>(stage: &str, window: F) -> Option<TimeWindow>
+where
+    F: Fn(&'static Schedule) -> Option<&'static str>,
+{
+    match cfg_from_file() {
+        Ok(cfg) => match cfg.schedule.as_ref().and_then(window)?.parse() {
+            Ok(_window) => Some(_window),
+            Err(err) => {
+                warn!("Ignoring time window for {}: {:?}", stage, err);
+                None
+            }
+        },
+        Err(err) => {
+            warn!(
+                "Cannot determine time window for {} from configuration: {:?}",
+                stage, err
+            );
+            None
+        }
+    }
+}
+
 /// Content of the configuration file
 #[derive(serde::Deserialize, Debug, Default)]
 struct CfgFromFile {
     working_dir: Option<PathBuf>,
+    stability_window: Option<u64>,
+    av_sync_threshold: Option<u64>,
+    crash_reports: Option<bool>,
     decoding: Option<Decoding>,
     cutting: Option<Cutting>,
+    schedule: Option<Schedule>,
+    proxy: Option<Proxy>,
+    upload: Option<Upload>,
+    tool_env: Option<HashMap<String, String>>,
+    notifications: Option<Vec<Notification>>,
 }
 #[derive(serde::Deserialize, Debug, Default)]
 struct Decoding {
@@ -185,6 +457,101 @@ struct Cutting {
     cutlist_at_access_token: Option<String>,
 }
 
+/// Settings for uploading cut videos to rclone remotes
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct Upload {
+    remote: Option<String>,
+    routes: Option<Vec<UploadRoute>>,
+    bandwidth_limit: Option<String>,
+    retries: Option<u8>,
+}
+#[derive(serde::Deserialize, Debug, Default)]
+struct UploadRoute {
+    pattern: String,
+    remote: String,
+}
+impl Upload {
+    /// Returns the rclone remote (incl. path) a video is uploaded to. That is
+    /// the remote of the first route whose pattern matches the file name of the
+    /// video, or the default remote if no route matches. Routes with invalid
+    /// patterns are ignored with a warning
+    pub fn remote(&self, file_name: &str) -> Option<&str> {
+        for route in self.routes.iter().flatten() {
+            match Regex::new(&route.pattern) {
+                Ok(re) if re.is_match(file_name) => return Some(&route.remote),
+                Ok(_) => (),
+                Err(err) => warn!(
+                    "Ignoring upload route with invalid pattern \"{}\": {:?}",
+                    route.pattern, err
+                ),
+            }
+        }
+        self.remote.as_deref()
+    }
+
+    /// Returns the bandwidth limit for uploads (in the format of rclone's
+    /// --bwlimit option, e.g. "2M")
+    pub fn bandwidth_limit(&self) -> Option<&str> {
+        self.bandwidth_limit.as_deref()
+    }
+
+    /// Returns the number of retries of failed uploads
+    pub fn retries(&self) -> Option<u8> {
+        self.retries
+    }
+}
+
+/// Settings for notifications: A backend (incl. its parameters) and the kinds
+/// of events it sends notifications for. If no kinds are configured,
+/// notifications are sent for all events
+#[derive(serde::Deserialize, Debug)]
+pub struct Notification {
+    #[serde(flatten)]
+    backend: NotificationBackend,
+    events: Option<Vec<EventKind>>,
+}
+impl Notification {
+    pub fn backend(&self) -> &NotificationBackend {
+        &self.backend
+    }
+
+    pub fn events(&self) -> Option<&[EventKind]> {
+        self.events.as_deref()
+    }
+}
+/// Notification backends with their parameters
+#[derive(serde::Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NotificationBackend {
+    Desktop,
+    Email {
+        to: String,
+    },
+    Ntfy {
+        server: Option<String>,
+        topic: String,
+    },
+    Telegram {
+        bot_token: String,
+        chat_id: String,
+    },
+    Webhook {
+        url: String,
+    },
+}
+#[derive(serde::Deserialize, Debug, Default)]
+struct Proxy {
+    all: Option<String>,
+    http: Option<String>,
+    https: Option<String>,
+    no_proxy: Option<String>,
+}
+#[derive(serde::Deserialize, Debug, Default)]
+struct Schedule {
+    decoding: Option<String>,
+    cutting: Option<String>,
+}
+
 /// Retrieve the content of the configuration file. That is only done once. The
 /// result is stored in a static variable.
 fn cfg_from_file() -> anyhow::Result<&'static CfgFromFile> {
@@ -192,8 +559,8 @@ fn cfg_from_file() -> anyhow::Result<&'static CfgFromFile> {
     CFG_FROM_FILE.get_or_try_init(|| {
         // Assemble path for config file: Get standard configuration directory of
         // the OS (if that's available) and append the otr config file name
-        let path = if let Some(cfg_dir) = dirs::config_dir() {
-            cfg_dir.join(CFG_FILENAME)
+        let path = if let Some(_path) = file_path() {
+            _path
         } else {
             return Err(anyhow!(
                 "Could not determine path of configuration directory for this OS"
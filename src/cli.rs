@@ -2,16 +2,19 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::video::{ArchiveAction, Stage};
+
+use chrono::NaiveDate;
 use clap::{Parser, Subcommand};
 use indoc::indoc;
 use once_cell::sync::OnceCell;
 use otr_utils::cutting::{CutlistAccessType, CutlistID, CutlistRating};
+use regex::Regex;
 use std::path::{Path, PathBuf};
+use std::{ffi::OsString, time::Duration};
 
-/// Converts cli parameters for cut list access into CutlistAccessType.
-/// Note: Calling this function does only make sense for some sub commands.
-///       If it is called when otr is called with a sub command that does
-///       not have appropriate parameters, the function panics!
+/// Converts cli parameters for cut list access into CutlistAccessType. For sub
+/// commands without cut list parameters, CutlistAccessType::Auto is returned
 pub fn cutlist_access_type() -> CutlistAccessType<'static> {
     match &args().command {
         Commands::Cut {
@@ -30,19 +33,52 @@ pub fn cutlist_access_type() -> CutlistAccessType<'static> {
                 CutlistAccessType::Auto
             }
         }
-        Commands::Decode { .. } => {
-            panic!("Sub command 'decode' does not have cut list access type as parameter")
-        }
-        Commands::Process { .. } => CutlistAccessType::Auto,
+        _ => CutlistAccessType::Auto,
+    }
+}
+
+/// Returns the path of the EDL file that was submitted as cut list. For sub
+/// commands without that parameter, None is returned
+pub fn cutlist_edl() -> Option<&'static Path> {
+    match &args().command {
+        Commands::Cut { edl, .. } => edl.as_deref(),
+        _ => None,
     }
 }
 
 /// Returns true if otr was called with sub command "cut", otherwise false
 pub fn is_cut_command() -> bool {
-    if let Commands::Cut { .. } = args().command {
-        return true;
+    matches!(args().command, Commands::Cut { .. })
+}
+
+/// Returns true if flag --no-verify was set by the user. For sub commands
+/// without that flag, false is returned
+pub fn no_verify() -> bool {
+    match &args().command {
+        Commands::Cut { no_verify, .. } => *no_verify,
+        Commands::Process { no_verify, .. } => *no_verify,
+        _ => false,
+    }
+}
+
+/// Returns true if flag --wait was set by the user. For sub commands without
+/// that flag, false is returned
+pub fn wait() -> bool {
+    match &args().command {
+        Commands::Cut { wait, .. } => *wait,
+        Commands::Decode { wait, .. } => *wait,
+        Commands::Process { wait, .. } => *wait,
+        _ => false,
+    }
+}
+
+/// Returns the path of the cut video if the user wants to cut a video outside of
+/// the working directories (option --in-place-out). Otherwise, None is returned
+pub fn in_place_out() -> Option<&'static Path> {
+    match &args().command {
+        Commands::Cut { in_place_out, .. } => in_place_out.as_deref(),
+        _ => None,
     }
-    false
 }
 
 /// Returns true if otr was called with sub command "decode", otherwise false
@@ -53,6 +89,127 @@ pub fn is_decode_command() -> bool {
     false
 }
 
+/// Returns true if otr was called with sub command "clean", otherwise false
+pub fn is_clean_command() -> bool {
+    matches!(args().command, Commands::Clean { .. })
+}
+
+/// Returns true if flag --dry-run was set by the user. For sub commands without
+/// that flag, false is returned
+pub fn dry_run() -> bool {
+    matches!(args().command, Commands::Clean { dry_run: true })
+}
+
+/// Returns destination, filter and minimum recording date of sub command
+/// "export". For other sub commands, None is returned
+pub fn export_params() -> Option<(&'static Path, Option<&'static Regex>, Option<NaiveDate>)> {
+    match &args().command {
+        Commands::Export {
+            dest,
+            filter,
+            newer_than,
+            ..
+        } => Some((dest, filter.as_ref(), *newer_than)),
+        _ => None,
+    }
+}
+
+/// Returns the action of sub command "archive". If no action was given, the
+/// archived videos are listed. For other sub commands, None is returned
+pub fn archive_action() -> Option<ArchiveAction> {
+    match &args().command {
+        Commands::Archive {
+            list,
+            prune,
+            older_than,
+            size_limit,
+            ..
+        } => Some(if *list {
+            ArchiveAction::List
+        } else if *prune {
+            // clap makes sure that --older-than is set together with --prune
+            ArchiveAction::Prune(older_than.unwrap())
+        } else if let Some(gb) = size_limit {
+            ArchiveAction::SizeLimit(*gb)
+        } else {
+            ArchiveAction::List
+        }),
+        _ => None,
+    }
+}
+
+/// Returns the tag that videos must have to be considered by sub commands
+/// "archive" and "export". For other sub commands, None is returned
+pub fn tag_filter() -> Option<&'static str> {
+    match &args().command {
+        Commands::Archive { tag, .. } | Commands::Export { tag, .. } => tag.as_deref(),
+        _ => None,
+    }
+}
+
+/// Returns the video (key or path), the tags and whether the tags shall be
+/// removed if otr was called with sub command "tag". For other sub commands,
+/// None is returned
+pub fn tag_params() -> Option<(&'static str, &'static [String], bool)> {
+    match &args().command {
+        Commands::Tag {
+            video,
+            tags,
+            remove,
+        } => Some((video, tags, *remove)),
+        _ => None,
+    }
+}
+
+/// Returns which data shall be removed (credentials, caches, state) if otr was
+/// called with sub command "purge". For other sub commands, None is returned
+pub fn purge_params() -> Option<(bool, bool, bool)> {
+    match &args().command {
+        Commands::Purge {
+            credentials,
+            caches,
+            state,
+        } => Some((*credentials, *caches, *state)),
+        _ => None,
+    }
+}
+
+/// Returns the path of the video and the path of the cut list file if otr was
+/// called with sub command "detect-cuts". If no cut list file was given, the
+/// path of the video with the additional extension ".cutlist" is used. For other
+/// sub commands, None is returned
+pub fn detect_cuts_params() -> Option<(&'static Path, PathBuf)> {
+    match &args().command {
+        Commands::DetectCuts { video, cutlist } => Some((
+            video.as_path(),
+            cutlist.clone().unwrap_or_else(|| {
+                let mut cutlist = video.as_os_str().to_os_string();
+                cutlist.push(".cutlist");
+                PathBuf::from(cutlist)
+            }),
+        )),
+        _ => None,
+    }
+}
+
+/// Returns true if otr was called with sub command "doctor", otherwise false
+pub fn is_doctor_command() -> bool {
+    matches!(args().command, Commands::Doctor)
+}
+
+/// Returns Some(force) if otr was called with sub command "setup" and flag
+/// --defaults, whereas force tells whether an existing configuration file may
+/// be overwritten. Otherwise, None is returned
+pub fn setup_defaults() -> Option<bool> {
+    match &args().command {
+        Commands::Setup {
+            defaults: true,
+            force,
+        } => Some(*force),
+        _ => None,
+    }
+}
+
 /// Returns true if otr was called with sub command "process", otherwise false
 pub fn is_process_command() -> bool {
     if let Commands::Process { .. } = args().command {
@@ -61,27 +218,147 @@ pub fn is_process_command() -> bool {
     false
 }
 
-/// Returns cut list rating
+/// Returns true if otr was called with sub command "watch", otherwise false
+pub fn is_watch_command() -> bool {
+    if let Commands::Watch { .. } = args().command {
+        return true;
+    }
+    false
+}
+
+/// Returns debounce time and retry interval if otr was called with sub command
+/// "watch". Otherwise, None is returned
+pub fn watch_params() -> Option<(Duration, Duration)> {
+    match &args().command {
+        Commands::Watch {
+            debounce,
+            retry_interval,
+            ..
+        } => Some((*debounce, *retry_interval)),
+        _ => None,
+    }
+}
+
+/// Returns true if flag --report was set by the user. For sub commands without
+/// that flag, false is returned
+pub fn report() -> bool {
+    match &args().command {
+        Commands::Process { report, .. } | Commands::Watch { report, .. } => *report,
+        _ => false,
+    }
+}
+
+/// Returns true if otr runs as child process that processes a single video
+/// (see option --per-video-timeout), otherwise false
+pub fn is_child() -> bool {
+    matches!(args().command, Commands::Process { child: true, .. })
+}
+
+/// Returns cut list rating. For sub commands without that parameter, None is
+/// returned
 pub fn cutlist_rating() -> Option<CutlistRating> {
     match &args().command {
         Commands::Cut { rating, .. } => *rating,
-        Commands::Decode { .. } => {
-            panic!("Sub command 'decode' does not have cut list rating as parameter")
-        }
-        Commands::Process { .. } => {
-            panic!("Sub command 'process' does not have cut list rating as parameter")
-        }
+        _ => None,
     }
 }
 
-/// Returns minimum cut list rating
+/// Returns minimum cut list rating. For sub commands without that parameter,
+/// None is returned
 pub fn min_cutlist_rating() -> Option<CutlistRating> {
     match &args().command {
         Commands::Cut { min_rating, .. } => *min_rating,
-        Commands::Decode { .. } => {
-            panic!("Sub command 'decode' does not have minimum cut list rating as parameter")
-        }
         Commands::Process { min_rating, .. } => *min_rating,
+        Commands::Watch { min_rating, .. } => *min_rating,
+        _ => None,
+    }
+}
+
+/// Returns the maximum time the processing of a single video may take. For sub
+/// commands without that parameter, None is returned
+pub fn per_video_timeout() -> Option<Duration> {
+    match &args().command {
+        Commands::Process {
+            per_video_timeout, ..
+        } => *per_video_timeout,
+        _ => None,
+    }
+}
+
+/// Returns the time after which the cutting of videos is retried for which
+/// there was no cut list. For sub commands without that parameter, None is
+/// returned
+pub fn retry_after() -> Option<Duration> {
+    match &args().command {
+        Commands::Process { retry_after, .. } => {
+            retry_after.map(|hours| Duration::from_secs(hours * 60 * 60))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the command line arguments that are required to process a single
+/// video with sub command "process" in a child process. The parameters the user
+/// submitted are passed on, except the videos, the per-video timeout and the
+/// retry interval (the retry queue is managed by the parent process). The path
+/// of the video must be appended to the returned arguments
+pub fn process_args_for_child() -> Vec<OsString> {
+    let mut child_args: Vec<OsString> = vec!["process".into(), "--child".into()];
+
+    if let Commands::Process {
+        min_rating,
+        no_verify,
+        ..
+    } = &args().command
+    {
+        if let Some(_min_rating) = min_rating {
+            child_args.extend(["--min-rating".into(), _min_rating.to_string().into()]);
+        }
+        if *no_verify {
+            child_args.push("--no-verify".into());
+        }
+    }
+    if let Some(_stage) = args().fail_at {
+        child_args.extend(["--fail-at".into(), _stage.to_string().into()]);
+    }
+    if args().quiet {
+        child_args.push("--quiet".into());
+    }
+    for _ in 0..args().verbose {
+        child_args.push("--verbose".into());
+    }
+
+    child_args
+}
+
+/// Returns the environment variables that are required to process a single
+/// video with sub command "process" in a child process: The OTR access data the
+/// user submitted is passed via environment variables instead of command line
+/// arguments, since the command line of a process can be read by any user
+pub fn process_env_for_child() -> Vec<(&'static str, &'static str)> {
+    match &args().command {
+        Commands::Process {
+            user: Some(_user),
+            password: Some(_password),
+            ..
+        } => vec![("OTR_USER", _user), ("OTR_PASSWORD", _password)],
+        _ => vec![],
+    }
+}
+
+/// Returns the processing stage that shall fail deliberately
+pub fn fail_at() -> Option<Stage> {
+    args().fail_at
+}
+
+/// Returns the directory for temporary files that was set by the user. For sub
+/// commands without that parameter, None is returned
+#[cfg(target_os = "linux")]
+pub fn tmp_dir() -> Option<&'static Path> {
+    match &args().command {
+        Commands::Cut { tmp_dir, .. } => tmp_dir.as_deref(),
+        Commands::Process { tmp_dir, .. } => tmp_dir.as_deref(),
+        _ => None,
     }
 }
 
@@ -100,30 +377,37 @@ pub fn verbose() -> u8 {
 /// command required only one video, this video is returned in an array)
 pub fn videos() -> Vec<&'static Path> {
     match &args().command {
-        Commands::Cut { video, .. } => vec![video.as_path()],
+        Commands::Cut { videos, .. } => videos.iter().map(|p| p.as_path()).collect(),
         Commands::Decode { video, .. } => vec![video.as_path()],
         Commands::Process { videos, .. } => videos.iter().map(|p| p.as_path()).collect(),
+        Commands::Watch { .. } => vec![],
+        Commands::Archive { .. }
+        | Commands::Clean { .. }
+        | Commands::DetectCuts { .. }
+        | Commands::Doctor
+        | Commands::Export { .. }
+        | Commands::Purge { .. }
+        | Commands::Setup { .. }
+        | Commands::Tag { .. } => vec![],
     }
 }
 
-/// Returns OTR access data (user, password).
-/// Note: Calling this function does only make sense for some sub commands.
-///       If it is called when otr is called with a sub command that does
-///       not have appropriate parameters, the function panics!
+/// Returns OTR access data (user, password). For sub commands without these
+/// parameters, None is returned
 pub fn otr_access_data() -> Option<(&'static str, &'static str)> {
-    match &args().command {
-        Commands::Cut { .. } => {
-            panic!("Sub command 'cut' does not have OTR access data as parameters")
-        }
-        Commands::Decode { user, password, .. } | Commands::Process { user, password, .. } => {
-            // Note: Either both, user and password are Some(...) or None.
-            //       This is ensured by the clap configuration
-            if user.is_some() {
-                Some((user.as_ref().unwrap(), password.as_ref().unwrap()))
-            } else {
-                None
-            }
-        }
+    let (user, password) = match &args().command {
+        Commands::Decode { user, password, .. } => (user, password),
+        Commands::Process { user, password, .. } => (user, password),
+        Commands::Watch { user, password, .. } => (user, password),
+        _ => return None,
+    };
+
+    // Note: Either both, user and password are Some(...) or None. This is
+    //       ensured by the clap configuration
+    if let (Some(_user), Some(_password)) = (user, password) {
+        Some((_user, _password))
+    } else {
+        None
     }
 }
 
@@ -158,6 +442,46 @@ struct Args {
         help = "Switch off output completely, even error messages will not be displayed"
     )]
     quiet: bool,
+    #[arg(
+        global = true,
+        long = "fail-at",
+        env = "OTR_FAIL_AT",
+        value_name = "stage",
+        hide = true,
+        help = indoc! {"
+        Let the processing of videos fail deliberately at the given stage (\"decode\" or
+        \"cut\") without executing it. This allows testing the error handling of setups
+        around otr"}
+    )]
+    fail_at: Option<Stage>,
+}
+
+/// Parses a duration string, such as "90m" or "1h30m". Supported units are h
+/// (hours), m (minutes) and s (seconds). A number without unit is interpreted as
+/// seconds
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let re = Regex::new(r"^(?:(?P<h>\d+)h)?(?:(?P<m>\d+)m)?(?:(?P<s>\d+)s?)?$").unwrap();
+
+    let captures = match re.captures(s) {
+        Some(captures) if !s.is_empty() => captures,
+        _ => return Err(format!("\"{}\" is not a valid duration", s)),
+    };
+
+    let mut secs: u64 = 0;
+    for (unit, factor) in [("h", 3600), ("m", 60), ("s", 1)] {
+        if let Some(value) = captures.name(unit) {
+            secs += value
+                .as_str()
+                .parse::<u64>()
+                .map_err(|err| format!("\"{}\" is not a valid duration: {}", s, err))?
+                * factor;
+        }
+    }
+    if secs == 0 {
+        return Err("Duration must be greater than zero".to_string());
+    }
+
+    Ok(Duration::from_secs(secs))
 }
 
 /// Command line arguments. The conversion into that structure is done once only.
@@ -173,14 +497,16 @@ fn args() -> &'static Args {
 enum Commands {
     #[command(
         name = "cut",
-        about = "Cut a video",
+        about = "Cut one or more videos",
         long_about = indoc! {"
-            Cut a video if possible. That is the case if ...
+            Cut one or more videos if possible. That is the case if ...
               (a) at least one cut list exists on cutlist.at, which is either selected
                   automatically, or an ID of a cut list is submitted, or
               (b) a cut list is given explicitly as sequence of intervals or as file.
+            If multiple videos are submitted, the cut list parameters apply to each of
+            them. An error while cutting a video does not stop the cutting of the others.
  
-            If the video was cut successfully, the corresponding files (i.e., the uncut
+            If a video was cut successfully, the corresponding files (i.e., the uncut
             and cut video files) are moved to the corresponding work (sub)directories"}
     )]
     Cut {
@@ -209,6 +535,16 @@ enum Commands {
             cutlist.at"}
         )]
         file: Option<PathBuf>,
+        #[arg(
+            long = "cutlist-edl",
+            value_name = "path_of_edl_file",
+            group = "input",
+            help = indoc! {"
+            Path of an EDL file (as used by MPlayer or Kodi). The segments that are marked
+            as cut (action 0) or commercial break (action 3) are removed from the video.
+            This option requires that only one video is submitted"}
+        )]
+        edl: Option<PathBuf>,
         #[arg(
             long = "cutlist-id",
             value_name = "cut_list_id",
@@ -230,8 +566,36 @@ enum Commands {
             help = "Rating of a self-created cut list"
         )]
         rating: Option<CutlistRating>,
-        #[arg(name = "video", help = "Path of video to be cut")]
-        video: PathBuf,
+        #[arg(
+            long = "no-verify",
+            help = "Do not verify the cut video (number of streams, duration) after cutting"
+        )]
+        no_verify: bool,
+        #[arg(
+            long = "in-place-out",
+            value_name = "path",
+            help = indoc! {"
+            Cut a single decoded video outside of the working directories and write the
+            cut video to PATH. No video is moved to or from the working directories"}
+        )]
+        in_place_out: Option<PathBuf>,
+        #[arg(
+            long = "wait",
+            help = "If another instance of otr is processing videos, wait until it is finished"
+        )]
+        wait: bool,
+        #[cfg(target_os = "linux")]
+        #[arg(
+            long = "tmp-dir",
+            value_name = "path",
+            help = indoc! {"
+            Directory for temporary files (e.g., intermediate results of cutting) for this
+            run. otr creates the sub directory OTR there. Default is the cache directory of
+            the user"}
+        )]
+        tmp_dir: Option<PathBuf>,
+        #[arg(name = "videos", required = true, help = "Paths of videos to be cut")]
+        videos: Vec<PathBuf>,
     },
     #[command(
         name = "decode",
@@ -244,6 +608,7 @@ enum Commands {
         #[arg(
             short = 'u',
             long = "user",
+            env = "OTR_USER",
             help = "User name for Online TV Recorder (overwrites configuration file content)",
             requires("password")
         )]
@@ -251,13 +616,192 @@ enum Commands {
         #[arg(
             short = 'p',
             long = "password",
+            env = "OTR_PASSWORD",
+            hide_env_values = true,
             help = "Password for Online TV Recorder (overwrites configuration file content)",
             requires("user")
         )]
         password: Option<String>,
+        #[arg(
+            long = "wait",
+            help = "If another instance of otr is processing videos, wait until it is finished"
+        )]
+        wait: bool,
         #[arg(name = "video", help = "Path of video to be decoded")]
         video: PathBuf,
     },
+    #[command(
+        name = "archive",
+        about = "Manage the archive of decoded videos",
+        long_about = indoc! {"
+            Manage the archive directory, where decoded videos are moved to after they
+            were cut: List the archived videos (default), remove the videos that were
+            archived more than a number of days ago, or remove the oldest videos until
+            the archive does not require more than a given space. Videos are removed
+            together with their journal files"}
+    )]
+    Archive {
+        #[arg(long = "list", group = "action", help = "List the archived videos")]
+        list: bool,
+        #[arg(
+            long = "prune",
+            group = "action",
+            requires = "older_than",
+            help = "Remove archived videos that are older than --older-than"
+        )]
+        prune: bool,
+        #[arg(
+            long = "older-than",
+            value_name = "days",
+            requires = "prune",
+            help = "Number of days after which archived videos are removed"
+        )]
+        older_than: Option<u64>,
+        #[arg(
+            long = "size-limit",
+            value_name = "GB",
+            group = "action",
+            help = "Remove the oldest archived videos until the archive requires at most this space"
+        )]
+        size_limit: Option<f64>,
+        #[arg(
+            long = "tag",
+            value_name = "tag",
+            help = "Only archived videos with this tag are considered"
+        )]
+        tag: Option<String>,
+    },
+    #[command(
+        name = "clean",
+        about = "Remove temporary and stale artefacts",
+        long_about = indoc! {"
+            Remove temporary and stale artefacts of cutting: Cutting directories and
+            generated cut list files in the directory for temporary files, and FFMS2
+            index files in the working (sub) directories. Do not run this command while
+            another instance of otr is cutting videos"}
+    )]
+    Clean {
+        #[arg(
+            long = "dry-run",
+            help = "Only report the artefacts and the space that could be reclaimed"
+        )]
+        dry_run: bool,
+    },
+    #[command(
+        name = "detect-cuts",
+        about = "Detect commercial breaks and create a cut list (experimental)",
+        long_about = indoc! {"
+            Detect commercial breaks in a video and create a cut list file that removes
+            them. The file has the INI format of cutlist.at and can be used with
+            \"otr cut --cutlist-file\". This is experimental: Points in time where black
+            frames and silence coincide are considered as boundaries between segments,
+            and a sequence of short segments is considered as commercial break. Check
+            the result before using the cut list. ffmpeg and ffprobe are required"}
+    )]
+    DetectCuts {
+        #[arg(
+            long = "cutlist-file",
+            value_name = "path_of_cut_list_file",
+            help = indoc! {"
+            Path of the cut list file to be created. Default: Path of the video with the
+            additional extension \".cutlist\""}
+        )]
+        cutlist: Option<PathBuf>,
+        #[arg(help = "Video to be analyzed")]
+        video: PathBuf,
+    },
+    #[command(
+        name = "doctor",
+        about = "Check the setup of otr",
+        long_about = indoc! {"
+            Check the setup of otr and report problems: Whether the configuration file can
+            be read, whether the working (sub) directories exist (they are created if
+            necessary), are writable and are on the same file system, and whether the
+            tools required for cutting are installed"}
+    )]
+    Doctor,
+    #[command(
+        name = "export",
+        about = "Copy cut videos to external storage",
+        long_about = indoc! {"
+            Copy cut videos to a destination directory (e.g., on an external disk). Each
+            copy is verified with the MD5 checksum of the original. Incomplete copies of a
+            previous export are resumed, complete copies are skipped"}
+    )]
+    Export {
+        #[arg(long = "dest", value_name = "path", help = "Destination directory")]
+        dest: PathBuf,
+        #[arg(
+            long = "filter",
+            value_name = "regex",
+            help = "Regular expression: Only cut videos whose file name matches it are exported"
+        )]
+        filter: Option<Regex>,
+        #[arg(
+            long = "newer-than",
+            value_name = "YYYY-MM-DD",
+            help = "Only cut videos that were recorded on or after this date are exported"
+        )]
+        newer_than: Option<NaiveDate>,
+        #[arg(
+            long = "tag",
+            value_name = "tag",
+            help = "Only cut videos with this tag are exported"
+        )]
+        tag: Option<String>,
+    },
+    #[command(
+        name = "purge",
+        about = "Remove personal data and caches",
+        long_about = indoc! {"
+            Remove personal data and caches, e.g. before handing the machine over to
+            someone else: The credentials from the configuration file, the directory for
+            temporary files, and the retry queue in the working directory. Videos, their
+            tags and all other settings are kept"},
+        group(clap::ArgGroup::new("data").required(true).multiple(true))
+    )]
+    Purge {
+        #[arg(
+            long = "credentials",
+            group = "data",
+            help = "Remove OTR user, password and cutlist.at access token from the configuration file"
+        )]
+        credentials: bool,
+        #[arg(
+            long = "caches",
+            group = "data",
+            help = "Remove the directory for temporary files"
+        )]
+        caches: bool,
+        #[arg(
+            long = "state",
+            group = "data",
+            help = "Remove the retry queue from the working directory"
+        )]
+        state: bool,
+    },
+    #[command(
+        name = "setup",
+        about = "Set up otr",
+        long_about = indoc! {"
+            Set up otr: With --defaults, a default configuration file is written, where
+            the working directory is set to the default of the OS. Afterwards, only the
+            access data for Online TV Recorder need to be added to it"}
+    )]
+    Setup {
+        #[arg(
+            long = "defaults",
+            required = true,
+            help = "Write the default configuration file"
+        )]
+        defaults: bool,
+        #[arg(
+            long = "force",
+            requires = "defaults",
+            help = "Overwrite an existing configuration file"
+        )]
+        force: bool,
+    },
     #[command(
         name = "process",
         about = "Decode and cut all videos",
@@ -273,6 +817,7 @@ enum Commands {
         #[arg(
             short = 'u',
             long = "user",
+            env = "OTR_USER",
             help = "User name for Online TV Recorder (overwrites configuration file content)",
             requires("password")
         )]
@@ -280,6 +825,8 @@ enum Commands {
         #[arg(
             short = 'p',
             long = "password",
+            env = "OTR_PASSWORD",
+            hide_env_values = true,
             help = "Password for Online TV Recorder (overwrites configuration file content)",
             requires = "user"
         )]
@@ -290,6 +837,141 @@ enum Commands {
             help = "Minímum rating a cut list must have for being used to cut the video"
         )]
         min_rating: Option<CutlistRating>,
+        #[arg(
+            long = "per-video-timeout",
+            value_name = "duration",
+            value_parser = parse_duration,
+            help = indoc! {"
+            Maximum time that decoding and cutting of a single video may take, such as
+            \"90m\" or \"1h30m\" (supported units are h, m and s). If the processing of a
+            video takes longer, it is aborted and otr continues with the next video"}
+        )]
+        per_video_timeout: Option<Duration>,
+        #[arg(
+            long = "retry-after",
+            value_name = "hours",
+            help = indoc! {"
+            Number of hours after which cutting is retried for videos for which there
+            was no cut list. Until then, these videos are skipped, and missing cut lists
+            are not treated as errors"}
+        )]
+        retry_after: Option<u64>,
+        #[arg(
+            long = "no-verify",
+            help = "Do not verify the cut video (number of streams, duration) after cutting"
+        )]
+        no_verify: bool,
+        #[arg(
+            long = "report",
+            help = "Write a report about the processed videos as HTML file into the working directory"
+        )]
+        report: bool,
+        #[arg(
+            long = "wait",
+            help = "If another instance of otr is processing videos, wait until it is finished"
+        )]
+        wait: bool,
+        #[arg(
+            long = "child",
+            hide = true,
+            help = "otr runs as child process that processes a single video (set by otr itself)"
+        )]
+        child: bool,
+        #[cfg(target_os = "linux")]
+        #[arg(
+            long = "tmp-dir",
+            value_name = "path",
+            help = indoc! {"
+            Directory for temporary files (e.g., intermediate results of cutting) for this
+            run. otr creates the sub directory OTR there. Default is the cache directory of
+            the user"}
+        )]
+        tmp_dir: Option<PathBuf>,
         videos: Vec<PathBuf>,
     },
+    #[command(
+        name = "tag",
+        about = "Tag videos",
+        long_about = indoc! {"
+            Add free-form tags to a video (e.g., \"comedy\" or \"keep-forever\"), remove
+            tags from it, or list its tags if no tags are given. The video is given by
+            its key or by the path of one of its files. A key must belong to a video in
+            the working directory or have tags already. Decoded and cut videos share
+            their tags. The archive and export sub commands can be restricted to videos
+            with a tag via --tag"}
+    )]
+    Tag {
+        #[arg(
+            long = "remove",
+            requires = "tags",
+            help = "Remove the tags from the video instead of adding them"
+        )]
+        remove: bool,
+        #[arg(help = "Key or path of the video")]
+        video: String,
+        #[arg(
+            help = "Tags to be added (or removed). If no tag is given, the tags of the video are listed"
+        )]
+        tags: Vec<String>,
+    },
+    #[command(
+        name = "watch",
+        about = "Decode and cut new videos automatically",
+        long_about = indoc! {"
+            Watch the working directory and its sub directory for encoded videos. As soon
+            as a new otrkey file appears and has not changed for the debounce time, all
+            videos are processed like with sub command process. Since there might not be
+            cut lists for new videos yet, processing is repeated after the retry interval.
+            otr watch runs until it is terminated"}
+    )]
+    Watch {
+        #[arg(
+            short = 'u',
+            long = "user",
+            env = "OTR_USER",
+            help = "User name for Online TV Recorder (overwrites configuration file content)",
+            requires("password")
+        )]
+        user: Option<String>,
+        #[arg(
+            short = 'p',
+            long = "password",
+            env = "OTR_PASSWORD",
+            hide_env_values = true,
+            help = "Password for Online TV Recorder (overwrites configuration file content)",
+            requires = "user"
+        )]
+        password: Option<String>,
+        #[arg(
+            long = "min-rating",
+            value_name = "min_cut_list_rating",
+            help = "Minímum rating a cut list must have for being used to cut the video"
+        )]
+        min_rating: Option<CutlistRating>,
+        #[arg(
+            long = "debounce",
+            value_name = "duration",
+            value_parser = parse_duration,
+            default_value = "30s",
+            help = "Time a new otrkey file must not have changed before it is processed"
+        )]
+        debounce: Duration,
+        #[arg(
+            long = "retry-interval",
+            value_name = "duration",
+            value_parser = parse_duration,
+            default_value = "1h",
+            help = indoc! {"
+            Time after which all videos are processed again, e.g. to cut videos for which
+            there were no cut lists before"}
+        )]
+        retry_interval: Duration,
+        #[arg(
+            long = "report",
+            help = indoc! {"
+            Write a report about the processed videos as HTML file into the working
+            directory after each run"}
+        )]
+        report: bool,
+    },
 }
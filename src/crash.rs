@@ -0,0 +1,109 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{cfg, video};
+
+use chrono::Local;
+use const_format::formatcp;
+use std::{
+    backtrace::Backtrace,
+    env, fs,
+    panic::{self, PanicHookInfo},
+    path::PathBuf,
+};
+
+/// URL to create a new issue for otr. The path "/-/issues/new" (and the query
+/// parameter "issue[title]" in issue_url) are specific to GitLab. Thus, this
+/// requires that repository in Cargo.toml points to a GitLab project
+const NEW_ISSUE_URL: &str = formatcp!("{}/-/issues/new", env!("CARGO_PKG_REPOSITORY"));
+
+/// Installs a panic hook that writes a crash report before the default panic
+/// handling takes place. The report is written to the directory for temporary
+/// files, and the user is asked to submit it via a prefilled issue. The report
+/// is anonymized: It contains where otr panicked, the backtrace, the version
+/// and the capabilities of otr, and which settings are configured (but not
+/// their values)
+pub fn install_reporter() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Some(path) => eprintln!(
+                "otr crashed. A crash report was written to \"{}\". Please check it and \
+                 attach it to an issue at:\n{}",
+                path.display(),
+                issue_url(info)
+            ),
+            None => eprintln!("otr crashed. A crash report could not be written"),
+        }
+        default_hook(info);
+    }));
+}
+
+/// Writes the crash report and returns its path. None is returned if the
+/// report could not be written
+fn write_report(info: &PanicHookInfo) -> Option<PathBuf> {
+    let dir = video::tmp_dir()?;
+    fs::create_dir_all(&dir).ok()?;
+    let path = dir.join(format!(
+        "crash-{}.txt",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    let report = format!(
+        "otr {}\n\
+         OS: {} ({})\n\
+         Features: {}\n\
+         Configured settings: {}\n\n\
+         {}\n\n\
+         Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        env::consts::OS,
+        env::consts::ARCH,
+        features().join(", "),
+        cfg::configured_settings().join(", "),
+        info,
+        Backtrace::force_capture()
+    );
+
+    fs::write(&path, report).ok()?;
+
+    Some(path)
+}
+
+/// Cargo features otr was built with
+fn features() -> Vec<&'static str> {
+    let mut features: Vec<&str> = vec![];
+    if cfg!(feature = "socks") {
+        features.push("socks");
+    }
+    features
+}
+
+/// URL to create a new issue whose title is prefilled with the location of the
+/// panic
+fn issue_url(info: &PanicHookInfo) -> String {
+    let title = match info.location() {
+        Some(location) => format!(
+            "Crash of otr {} at {}:{}",
+            env!("CARGO_PKG_VERSION"),
+            location.file(),
+            location.line()
+        ),
+        None => format!("Crash of otr {}", env!("CARGO_PKG_VERSION")),
+    };
+
+    format!("{}?issue[title]={}", NEW_ISSUE_URL, percent_encode(&title))
+}
+
+/// Percent-encodes a string for the use in a URL query
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,199 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::probe;
+
+use anyhow::{anyhow, Context};
+use otr_utils::cutting::CutlistAccessType;
+use std::{fs, path::Path};
+
+/// Prefix of the sections of cut list files that contain the cuts
+const CUT_SECTION_PREFIX: &str = "Cut";
+
+/// Determines the intervals (start and end in seconds) of video that are kept
+/// if it is cut with the given cut list. This is only possible if the cut list
+/// is given as intervals or as file. Cut lists that are retrieved from
+/// cutlist.at are selected by otr-utils and not visible to otr. In that case,
+/// None is returned.
+/// Parsing cut lists is the job of otr-utils, but it does not expose the
+/// intervals of a cut list (its cut list type is private). Thus, the little
+/// that otr needs to verify cut videos is parsed here: Intervals strings
+/// ("times:[...]" or "frames:[...]") and, from cut list files, the sections
+/// "[Cut<N>]" with the keys "Start" and "Duration" or "StartFrame" and
+/// "DurationFrames". All other sections and keys are ignored
+pub fn kept_intervals(
+    video: &Path,
+    access_type: &CutlistAccessType,
+) -> anyhow::Result<Option<Vec<(f64, f64)>>> {
+    match access_type {
+        CutlistAccessType::Direct(intervals) => {
+            if let Some(intervals) = intervals.strip_prefix("times:") {
+                Ok(Some(parse_intervals(intervals, parse_time)?))
+            } else if let Some(intervals) = intervals.strip_prefix("frames:") {
+                let frame_rate = probe::frame_rate(video)?;
+                Ok(Some(
+                    parse_intervals(intervals, parse_number)?
+                        .into_iter()
+                        .map(|(start, end)| (start / frame_rate, end / frame_rate))
+                        .collect(),
+                ))
+            } else {
+                Err(anyhow!("\"{}\" is not a valid intervals string", intervals))
+            }
+        }
+        CutlistAccessType::File(file) => {
+            let content = fs::read_to_string(file)
+                .with_context(|| format!("Could not read cut list file \"{}\"", file.display()))?;
+            let frame_rate = || probe::frame_rate(video);
+            Ok(Some(cuts_from_ini(&content, frame_rate).with_context(
+                || format!("Could not parse cut list file \"{}\"", file.display()),
+            )?))
+        }
+        CutlistAccessType::Auto | CutlistAccessType::ID(_) => Ok(None),
+    }
+}
+
+/// Parses a sequence of intervals such as "[<START>,<END>][<START>,<END>]".
+/// parse converts a single boundary into seconds (or frames)
+fn parse_intervals<F>(intervals: &str, parse: F) -> anyhow::Result<Vec<(f64, f64)>>
+where
+    F: Fn(&str) -> anyhow::Result<f64>,
+{
+    let mut kept: Vec<(f64, f64)> = vec![];
+
+    for interval in intervals.split_inclusive(']') {
+        let (start, end) = interval
+            .strip_prefix('[')
+            .and_then(|interval| interval.strip_suffix(']'))
+            .and_then(|interval| interval.split_once(','))
+            .ok_or_else(|| anyhow!("\"{}\" is not a valid interval", interval))?;
+        let (start, end) = (parse(start.trim())?, parse(end.trim())?);
+        if end < start {
+            return Err(anyhow!("Interval \"{}\" ends before it starts", interval));
+        }
+        kept.push((start, end));
+    }
+
+    if kept.is_empty() {
+        return Err(anyhow!("\"{}\" does not contain any interval", intervals));
+    }
+
+    Ok(kept)
+}
+
+/// Extracts the cuts (start and end in seconds) from the content of a cut list
+/// file in INI format, i.e. from the sections "[Cut<N>]" with the entries
+/// "Start" and "Duration" (in seconds) or "StartFrame" and "DurationFrames".
+/// frame_rate is only called if the cuts are given as frames
+fn cuts_from_ini<F>(content: &str, frame_rate: F) -> anyhow::Result<Vec<(f64, f64)>>
+where
+    F: Fn() -> anyhow::Result<f64>,
+{
+    // Start and duration in seconds and in frames per cut section
+    let mut cuts: Vec<[Option<f64>; 4]> = vec![];
+
+    let mut in_cut_section = false;
+    for line in content.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            in_cut_section = section
+                .strip_prefix(CUT_SECTION_PREFIX)
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+            if in_cut_section {
+                cuts.push([None; 4]);
+            }
+            continue;
+        }
+        if !in_cut_section {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let index = match key.trim() {
+            "Start" => 0,
+            "Duration" => 1,
+            "StartFrame" => 2,
+            "DurationFrames" => 3,
+            _ => continue,
+        };
+        if let Some(cut) = cuts.last_mut() {
+            cut[index] = Some(parse_number(value.trim())?);
+        }
+    }
+
+    if cuts.is_empty() {
+        return Err(anyhow!("Cut list does not contain any cut"));
+    }
+
+    let mut kept: Vec<(f64, f64)> = vec![];
+    for (i, cut) in cuts.into_iter().enumerate() {
+        match cut {
+            [Some(start), Some(duration), _, _] => kept.push((start, start + duration)),
+            [_, _, Some(start), Some(duration)] => {
+                let frame_rate = frame_rate()?;
+                kept.push((start / frame_rate, (start + duration) / frame_rate))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Cut {} does not have a start and a duration",
+                    i + 1
+                ))
+            }
+        }
+    }
+
+    Ok(kept)
+}
+
+/// Parses a time of the format H:MM:SS[.ssssss] into seconds
+fn parse_time(time: &str) -> anyhow::Result<f64> {
+    let fields: Vec<&str> = time.split(':').collect();
+    match fields[..] {
+        [hours, mins, secs] => {
+            Ok(parse_number(hours)? * 3600.0 + parse_number(mins)? * 60.0 + parse_number(secs)?)
+        }
+        _ => Err(anyhow!("\"{}\" is not a valid time", time)),
+    }
+}
+
+/// Parses a non-negative number
+fn parse_number(number: &str) -> anyhow::Result<f64> {
+    number
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .ok_or_else(|| anyhow!("\"{}\" is not a valid number", number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_intervals_accepts_time_intervals() {
+        let kept = parse_intervals(
+            "[0:00:10.500000,0:05:00.000000][1:00:00,1:00:30.25]",
+            parse_time,
+        )
+        .unwrap();
+        assert_eq!(kept, vec![(10.5, 300.0), (3600.0, 3630.25)]);
+
+        assert!(parse_intervals("[0:00:10,0:00:05]", parse_time).is_err());
+        assert!(parse_intervals("[0:00:10]", parse_time).is_err());
+        assert!(parse_intervals("", parse_time).is_err());
+    }
+
+    #[test]
+    fn cuts_from_ini_accepts_times_and_frames() {
+        let ini = "[General]\nNoOfCuts=2\n\n\
+                   [Cut0]\nStart=10.000000\nDuration=290.000000\n\n\
+                   [Cut1]\nStartFrame=90000\nDurationFrames=750\n\n\
+                   [Meta]\nCutlistId=1\n";
+        let kept = cuts_from_ini(ini, || Ok(25.0)).unwrap();
+        assert_eq!(kept, vec![(10.0, 300.0), (3600.0, 3630.0)]);
+
+        assert!(cuts_from_ini("[General]\nNoOfCuts=0\n", || Ok(25.0)).is_err());
+        assert!(cuts_from_ini("[Cut0]\nStart=10\n", || Ok(25.0)).is_err());
+    }
+}
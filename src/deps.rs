@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use std::{env, path::Path};
+
+/// External tools that are required for cutting videos
+pub const CUTTING_TOOLS: [&str; 3] = ["ffmpeg", "ffprobe", "ffmsindex"];
+
+/// Package managers otr can give installation hints for
+#[derive(Clone, Copy)]
+enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+    Brew,
+}
+impl PackageManager {
+    /// Determine the package manager of the system by checking which of the
+    /// supported package managers is available
+    fn detect() -> Option<Self> {
+        [
+            ("brew", PackageManager::Brew),
+            ("apt-get", PackageManager::Apt),
+            ("dnf", PackageManager::Dnf),
+            ("pacman", PackageManager::Pacman),
+        ]
+        .into_iter()
+        .find_map(|(cmd, mgr)| if is_installed(cmd) { Some(mgr) } else { None })
+    }
+
+    /// Command to install packages with the package manager
+    fn install_cmd(&self) -> &'static str {
+        match self {
+            PackageManager::Apt => "sudo apt-get install",
+            PackageManager::Dnf => "sudo dnf install",
+            PackageManager::Pacman => "sudo pacman -S",
+            PackageManager::Brew => "brew install",
+        }
+    }
+
+    /// Name of the package that contains a tool
+    fn package(&self, tool: &str) -> &'static str {
+        match (self, tool) {
+            (PackageManager::Apt, "ffmsindex") => "ffmsindex",
+            (_, "ffmsindex") => "ffms2",
+            (_, "rclone") => "rclone",
+            (_, "curl") => "curl",
+            (PackageManager::Apt, "notify-send") => "libnotify-bin",
+            (_, "notify-send") => "libnotify",
+            // sendmail is provided by mail transfer agents. msmtp is a simple
+            // one that only forwards mails to a mail server
+            (PackageManager::Apt | PackageManager::Pacman, "sendmail") => "msmtp-mta",
+            (_, "sendmail") => "msmtp",
+            // ffprobe is part of the ffmpeg package for all supported package
+            // managers
+            _ => "ffmpeg",
+        }
+    }
+}
+
+/// Checks if the given tools are installed (i.e., if they can be found in one
+/// of the directories of the PATH environment variable). If tools are missing,
+/// an error is returned that contains a hint how the missing tools can be
+/// installed
+pub fn check(tools: &[&str]) -> anyhow::Result<()> {
+    let missing: Vec<&str> = tools
+        .iter()
+        .filter(|tool| !is_installed(tool))
+        .copied()
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let hint = match PackageManager::detect() {
+        Some(mgr) => format!(
+            "Install them with \"{} {}\"",
+            mgr.install_cmd(),
+            missing
+                .iter()
+                .map(|tool| mgr.package(tool))
+                .unique()
+                .join(" ")
+        ),
+        None => "Install them with the package manager of your system".to_string(),
+    };
+
+    Err(anyhow!(
+        "Required tools are missing: {}. {}",
+        missing.join(", "),
+        hint
+    ))
+}
+
+/// Checks if an executable with the given name exists in one of the directories
+/// of the PATH environment variable
+fn is_installed(cmd: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| is_executable(&dir.join(cmd))))
+}
+
+/// Checks if a path is an executable file
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
@@ -0,0 +1,289 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{edl, probe};
+
+use anyhow::{anyhow, Context};
+use lazy_static::lazy_static;
+use log::*;
+use regex::Regex;
+use std::{fmt::Write, fs, path::Path, process::Command};
+
+/// Minimum duration (in seconds) of black frames and of silence that are
+/// considered as boundary between two segments of a video (e.g., between two
+/// commercials)
+const MIN_BOUNDARY_DURATION: f64 = 0.1;
+/// Maximum distance (in seconds) of two boundaries within a commercial break.
+/// Commercials are usually not longer than that
+const MAX_COMMERCIAL_LENGTH: f64 = 90.0;
+/// Minimum duration (in seconds) of a commercial break
+const MIN_BREAK_LENGTH: f64 = 60.0;
+
+/// Segments of a video (start and end in seconds)
+type Segments = Vec<(f64, f64)>;
+
+// Regular expressions for the output of the ffmpeg filters blackdetect and
+// silencedetect
+lazy_static! {
+    static ref RE_BLACK: Regex =
+        Regex::new(r"black_start:\s*(?<start>[\d.]+)\s+black_end:\s*(?<end>[\d.]+)").unwrap();
+    static ref RE_SILENCE_START: Regex =
+        Regex::new(r"silence_start:\s*(?<start>-?[\d.]+)").unwrap();
+    static ref RE_SILENCE_END: Regex = Regex::new(r"silence_end:\s*(?<end>[\d.]+)").unwrap();
+}
+
+/// Detects commercial breaks in a video and writes a cut list file in the INI
+/// format of cutlist.at that keeps all other parts of the video. The file can be
+/// used with "otr cut --cutlist-file". In addition, the cut list is printed as
+/// intervals string for "otr cut --cutlist". This is experimental: Boundaries
+/// between segments are points in time where black frames and silence coincide,
+/// and a sequence of short segments is considered as commercial break. The
+/// result should be checked before the cut list is used
+pub fn detect_cuts(video: &Path, cutlist: &Path) -> anyhow::Result<()> {
+    let file_name = video
+        .file_name()
+        .ok_or_else(|| anyhow!("\"{}\" is no valid path of a video", video.display()))?
+        .to_str()
+        .ok_or_else(|| anyhow!("File name of \"{}\" is not valid UTF-8", video.display()))?;
+    let size = fs::metadata(video)
+        .with_context(|| format!("Could not determine size of \"{}\"", video.display()))?
+        .len();
+    let duration = probe::duration(video)?;
+
+    info!(
+        "Analyzing \"{}\". This can take a while ...",
+        video.display()
+    );
+    let (blacks, silences) = black_and_silent_segments(video)?;
+
+    let boundaries = boundaries(&blacks, &silences);
+    debug!(
+        "Found {} boundaries in \"{}\"",
+        boundaries.len(),
+        video.display()
+    );
+
+    let breaks = breaks(&boundaries);
+    let intervals = edl::keep_intervals(&breaks, duration);
+    if intervals.is_empty() {
+        return Err(anyhow!(
+            "Could not detect anything to keep in \"{}\"",
+            video.display()
+        ));
+    }
+
+    fs::write(cutlist, cutlist_ini(file_name, size, &intervals)?)
+        .with_context(|| format!("Could not write cut list file \"{}\"", cutlist.display()))?;
+
+    info!(
+        "Detected {} commercial break(s) in \"{}\". Cut list written to \"{}\"",
+        breaks.len(),
+        video.display(),
+        cutlist.display()
+    );
+    // Cut lists are only submitted to cutlist.at if they are given as intervals.
+    // Thus, the cut list is printed in that format as well
+    info!(
+        "Cut list as intervals: \"times:{}\"",
+        intervals
+            .iter()
+            .map(|(start, end)| format!(
+                "[{},{}]",
+                edl::time_string(*start),
+                edl::time_string(*end)
+            ))
+            .collect::<String>()
+    );
+
+    Ok(())
+}
+
+/// Analyzes a video with the ffmpeg filters blackdetect and silencedetect and
+/// returns the segments with black frames and the segments with silence
+fn black_and_silent_segments(video: &Path) -> anyhow::Result<(Segments, Segments)> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-nostats", "-i"])
+        .arg(video)
+        .args([
+            "-vf",
+            &format!("blackdetect=d={}:pix_th=0.10", MIN_BOUNDARY_DURATION),
+            "-af",
+            &format!("silencedetect=n=-50dB:d={}", MIN_BOUNDARY_DURATION),
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .context("Could not execute ffmpeg")?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Could not analyze \"{}\": {}",
+            video.display(),
+            stderr.lines().last().unwrap_or_default().trim()
+        ));
+    }
+
+    Ok(segments_from_output(&stderr))
+}
+
+/// Extracts the segments with black frames and the segments with silence from
+/// the output (stderr) of the ffmpeg filters blackdetect and silencedetect
+fn segments_from_output(stderr: &str) -> (Segments, Segments) {
+    let mut blacks: Segments = vec![];
+    let mut silences: Segments = vec![];
+    let mut silence_start: Option<f64> = None;
+    for line in stderr.lines() {
+        if let Some(caps) = RE_BLACK.captures(line) {
+            if let (Ok(start), Ok(end)) = (caps["start"].parse(), caps["end"].parse()) {
+                blacks.push((start, end));
+            }
+        } else if let Some(caps) = RE_SILENCE_START.captures(line) {
+            silence_start = caps["start"]
+                .parse::<f64>()
+                .ok()
+                .map(|start| start.max(0.0));
+        } else if let Some(caps) = RE_SILENCE_END.captures(line) {
+            if let (Some(start), Ok(end)) = (silence_start.take(), caps["end"].parse()) {
+                silences.push((start, end));
+            }
+        }
+    }
+
+    (blacks, silences)
+}
+
+/// Boundaries between segments of a video: The middle of black segments that
+/// overlap with silence
+fn boundaries(blacks: &Segments, silences: &Segments) -> Vec<f64> {
+    blacks
+        .iter()
+        .filter(|(b_start, b_end)| {
+            silences
+                .iter()
+                .any(|(s_start, s_end)| s_start < b_end && b_start < s_end)
+        })
+        .map(|(start, end)| (start + end) / 2.0)
+        .collect()
+}
+
+/// Determines the commercial breaks (start and end in seconds) from the sorted
+/// boundaries between segments: Consecutive boundaries that are not more than
+/// MAX_COMMERCIAL_LENGTH apart form a commercial break, provided it lasts at
+/// least MIN_BREAK_LENGTH
+fn breaks(boundaries: &[f64]) -> Vec<(f64, f64)> {
+    let mut breaks: Vec<(f64, f64)> = vec![];
+
+    let mut i = 0;
+    while i < boundaries.len() {
+        let start = boundaries[i];
+        while i + 1 < boundaries.len() && boundaries[i + 1] - boundaries[i] <= MAX_COMMERCIAL_LENGTH
+        {
+            i += 1;
+        }
+        if boundaries[i] - start >= MIN_BREAK_LENGTH {
+            breaks.push((start, boundaries[i]));
+        }
+        i += 1;
+    }
+
+    breaks
+}
+
+/// Creates the content of a cut list file in the INI format of cutlist.at from
+/// time intervals. file_name and size are the name and the size (in bytes) of
+/// the video the cut list applies to
+fn cutlist_ini(file_name: &str, size: u64, intervals: &[(f64, f64)]) -> anyhow::Result<String> {
+    let mut ini = format!(
+        "[General]\n\
+         Application=otr\n\
+         Version={}\n\
+         IntendedCutApplicationName=ffmpeg\n\
+         NoOfCuts={}\n\
+         ApplyToFile={}\n\
+         OriginalFileSizeBytes={}\n\n\
+         [Meta]\n\n",
+        env!("CARGO_PKG_VERSION"),
+        intervals.len(),
+        file_name,
+        size
+    );
+    for (i, (start, end)) in intervals.iter().enumerate() {
+        write!(
+            ini,
+            "[Cut{}]\nStart={:.6}\nDuration={:.6}\n\n",
+            i,
+            start,
+            end - start
+        )?;
+    }
+
+    Ok(ini)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output of ffmpeg with the filters blackdetect and silencedetect
+    const FFMPEG_OUTPUT: &str = "\
+Input #0, avi, from 'Tatort_24.01.28_20-15_ard_90_TVOON_DE.mpg.HQ.avi':
+  Duration: 01:35:00.04, start: 0.000000, bitrate: 1744 kb/s
+[blackdetect @ 0x5612f3c0e2c0] black_start:0 black_end:1.5 black_duration:1.5
+[silencedetect @ 0x5612f3c0f100] silence_start: -0.00133333
+[silencedetect @ 0x5612f3c0f100] silence_end: 1.49 | silence_duration: 1.49133
+[blackdetect @ 0x5612f3c0e2c0] black_start:600.25 black_end:600.75 black_duration:0.5
+[silencedetect @ 0x5612f3c0f100] silence_start: 600.1
+[silencedetect @ 0x5612f3c0f100] silence_end: 600.7 | silence_duration: 0.6
+[blackdetect @ 0x5612f3c0e2c0] black_start:1000 black_end:1000.2 black_duration:0.2
+[out#0/null @ 0x5612f3c10a40] video:2222kB audio:16699kB subtitle:0kB
+";
+
+    #[test]
+    fn segments_are_parsed_from_ffmpeg_output() {
+        let (blacks, silences) = segments_from_output(FFMPEG_OUTPUT);
+        assert_eq!(blacks, vec![(0.0, 1.5), (600.25, 600.75), (1000.0, 1000.2)]);
+        // Negative start times are set to zero
+        assert_eq!(silences, vec![(0.0, 1.49), (600.1, 600.7)]);
+
+        // Silence that does not end before the video ends is ignored
+        let (_, silences) = segments_from_output("[silencedetect @ 0x1] silence_start: 5.5\n");
+        assert!(silences.is_empty());
+    }
+
+    #[test]
+    fn boundaries_need_black_and_silence() {
+        let (blacks, silences) = segments_from_output(FFMPEG_OUTPUT);
+        // The black segment at 1000s is not accompanied by silence
+        assert_eq!(boundaries(&blacks, &silences), vec![0.75, 600.5]);
+    }
+
+    #[test]
+    fn breaks_are_derived_from_boundaries() {
+        // Boundaries of a commercial break at 600s-780s, and single boundaries
+        // (e.g., scene changes) before and after it
+        let boundaries = [10.0, 600.0, 660.0, 700.0, 780.0, 1200.0, 1230.0];
+        assert_eq!(breaks(&boundaries), vec![(600.0, 780.0)]);
+
+        // Sequences of boundaries that are too short for a commercial break
+        assert!(breaks(&[100.0, 130.0]).is_empty());
+        assert!(breaks(&[]).is_empty());
+    }
+
+    #[test]
+    fn cutlist_ini_contains_all_cuts() {
+        let ini = cutlist_ini(
+            "Tatort_24.01.28_20-15_ard_90_TVOON_DE.mpg.HQ.avi",
+            1_234_567,
+            &[(0.0, 600.0), (780.0, 3000.5)],
+        )
+        .unwrap();
+        assert!(ini.starts_with("[General]\nApplication=otr\n"));
+        assert!(ini.contains("NoOfCuts=2\n"));
+        assert!(ini.contains("ApplyToFile=Tatort_24.01.28_20-15_ard_90_TVOON_DE.mpg.HQ.avi\n"));
+        assert!(ini.contains("OriginalFileSizeBytes=1234567\n"));
+        assert!(ini.contains("[Cut0]\nStart=0.000000\nDuration=600.000000\n"));
+        assert!(ini.contains("[Cut1]\nStart=780.000000\nDuration=2220.500000\n"));
+    }
+}
@@ -0,0 +1,111 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{
+    cfg, deps,
+    notify::Notifiers,
+    video::{self, DirCheck},
+};
+
+use anyhow::anyhow;
+use log::*;
+
+/// Results of the checks of sub command "doctor". Each finding is printed
+/// immediately, and errors and warnings are counted
+#[derive(Default)]
+struct Report {
+    errors: usize,
+    warnings: usize,
+}
+impl Report {
+    /// Add a successful check
+    fn ok<S: AsRef<str>>(&mut self, msg: S) {
+        info!("OK: {}", msg.as_ref());
+    }
+
+    /// Add a problem that does not prevent otr from working
+    fn warning<S: AsRef<str>>(&mut self, msg: S) {
+        warn!("{}", msg.as_ref());
+        self.warnings += 1;
+    }
+
+    /// Add a problem that prevents otr from working
+    fn error<S: AsRef<str>>(&mut self, msg: S) {
+        error!("{}", msg.as_ref());
+        self.errors += 1;
+    }
+}
+
+/// Checks the setup of otr (configuration file, working directories, required
+/// tools) and reports problems. While doing so, the working directories are
+/// created if they do not exist. An error is returned if at least one problem
+/// was found that prevents otr from working
+pub fn run() -> anyhow::Result<()> {
+    let mut report = Report::default();
+
+    // Configuration file (it is optional)
+    match cfg::file_path() {
+        Some(path) if path.exists() => match cfg::check() {
+            Ok(()) => report.ok(format!("Configuration file \"{}\"", path.display())),
+            Err(err) => report.error(format!("{:?}", err)),
+        },
+        Some(path) => report.ok(format!(
+            "No configuration file \"{}\": Default values are used",
+            path.display()
+        )),
+        None => report.warning("Could not determine path of configuration directory for this OS"),
+    }
+
+    // OTR access data is required for decoding
+    if cfg::otr_access_data().is_none() {
+        report.warning(
+            "OTR user and password are not configured: They must be submitted on the command line to decode videos",
+        );
+    }
+
+    // Working (sub) directories
+    for check in video::check_working_dirs() {
+        match check {
+            DirCheck::Ok(msg) => report.ok(msg),
+            DirCheck::Warning(msg) => report.warning(msg),
+            DirCheck::Error(msg) => report.error(msg),
+        }
+    }
+
+    // External tools that are required for cutting
+    match deps::check(&deps::CUTTING_TOOLS) {
+        Ok(()) => report.ok(format!(
+            "Required tools are installed: {}",
+            deps::CUTTING_TOOLS.join(", ")
+        )),
+        Err(err) => report.error(format!("{}", err)),
+    }
+
+    // External tools that are required for the configured notifications
+    let tools = Notifiers::from_cfg().tools();
+    if !tools.is_empty() {
+        match deps::check(&tools) {
+            Ok(()) => report.ok(format!(
+                "Tools for notifications are installed: {}",
+                tools.join(", ")
+            )),
+            Err(err) => report.error(format!("{}", err)),
+        }
+    }
+
+    if report.errors > 0 {
+        return Err(anyhow!(
+            "{} problem(s) and {} warning(s) found",
+            report.errors,
+            report.warnings
+        ));
+    }
+    if report.warnings > 0 {
+        info!("{} warning(s) found", report.warnings);
+    } else {
+        info!("No problems found");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::probe;
+
+use anyhow::{anyhow, Context};
+use std::{fs, path::Path};
+
+/// EDL actions that mark segments which are removed from the video: 0 (cut) and
+/// 3 (commercial break). Other actions (e.g., mute) are ignored
+const EDL_SKIP_ACTIONS: [u8; 2] = [0, 3];
+
+/// Converts an EDL file (as used by MPlayer or Kodi) into a cut list as
+/// sequence of time intervals (e.g., "times:[0:00:00.000000,0:05:30.000000]"),
+/// as it can be submitted with --cutlist. The segments that the EDL file marks
+/// as to be skipped are removed, all other parts of the video are kept. video
+/// is required to determine its duration
+pub fn intervals_from_edl(edl: &Path, video: &Path) -> anyhow::Result<String> {
+    let skips = skip_segments(
+        &fs::read_to_string(edl)
+            .with_context(|| format!("Could not read EDL file \"{}\"", edl.display()))?,
+    )
+    .with_context(|| format!("Could not parse EDL file \"{}\"", edl.display()))?;
+
+    let intervals = keep_intervals(&skips, probe::duration(video)?);
+    if intervals.is_empty() {
+        return Err(anyhow!(
+            "EDL file \"{}\" does not leave anything of the video",
+            edl.display()
+        ));
+    }
+
+    Ok(format!(
+        "times:{}",
+        intervals
+            .iter()
+            .map(|(from, to)| format!("[{},{}]", time_string(*from), time_string(*to)))
+            .collect::<String>()
+    ))
+}
+
+/// Converts the segments to be skipped (e.g., commercial breaks) into the
+/// intervals (start and end in seconds) that are kept of a video with the given
+/// duration (empty if nothing is kept). The skip segments can be unsorted and
+/// can overlap
+pub fn keep_intervals(skips: &[(f64, f64)], duration: f64) -> Vec<(f64, f64)> {
+    let mut skips = skips.to_vec();
+    skips.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // The parts of the video between the skip segments are kept
+    let mut intervals: Vec<(f64, f64)> = vec![];
+    let mut from = 0.0;
+    for (start, end) in skips.into_iter().chain([(duration, duration)]) {
+        let to = start.min(duration);
+        if to > from {
+            intervals.push((from, to));
+        }
+        from = from.max(end);
+    }
+
+    intervals
+}
+
+/// Extracts the segments to be skipped (start and end in seconds) from the
+/// content of an EDL file. Each line has the format "<START> <END> [<ACTION>]".
+/// Empty lines and comments (starting with "#") are ignored
+fn skip_segments(content: &str) -> anyhow::Result<Vec<(f64, f64)>> {
+    let mut skips: Vec<(f64, f64)> = vec![];
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let parse = |field: &str| -> anyhow::Result<f64> {
+            field
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite() && *value >= 0.0)
+                .ok_or_else(|| anyhow!("Line {}: \"{}\" is not a valid time", i + 1, field))
+        };
+        let (start, end, action) = match fields[..] {
+            [start, end] => (parse(start)?, parse(end)?, 0),
+            [start, end, action] => (
+                parse(start)?,
+                parse(end)?,
+                action.parse::<u8>().with_context(|| {
+                    format!("Line {}: \"{}\" is not a valid action", i + 1, action)
+                })?,
+            ),
+            _ => {
+                return Err(anyhow!(
+                    "Line {}: \"{}\" is not a valid EDL entry",
+                    i + 1,
+                    line
+                ))
+            }
+        };
+        if end < start {
+            return Err(anyhow!("Line {}: End is before start", i + 1));
+        }
+
+        if EDL_SKIP_ACTIONS.contains(&action) {
+            skips.push((start, end));
+        }
+    }
+
+    Ok(skips)
+}
+
+/// Formats a time (in seconds) as H:MM:SS.ssssss
+pub fn time_string(secs: f64) -> String {
+    let micros = (secs * 1_000_000.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:06}",
+        micros / 3_600_000_000,
+        micros / 60_000_000 % 60,
+        micros / 1_000_000 % 60,
+        micros % 1_000_000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_segments_are_parsed_from_edl() {
+        let content = "# Commercial breaks\n\
+                       \n\
+                       600.5 900.0 3\n\
+                       10 20\n\
+                       \t1200 1210 1\n\
+                       1500 1600 0\n";
+        assert_eq!(
+            skip_segments(content).unwrap(),
+            vec![(600.5, 900.0), (10.0, 20.0), (1500.0, 1600.0)]
+        );
+
+        assert!(skip_segments("10 5 0").is_err());
+        assert!(skip_segments("10 20 x").is_err());
+        assert!(skip_segments("10").is_err());
+        assert!(skip_segments("-10 20").is_err());
+        assert!(skip_segments("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn keep_intervals_complement_skip_segments() {
+        assert_eq!(
+            keep_intervals(&[(600.0, 900.0), (0.0, 10.0)], 1800.0),
+            vec![(10.0, 600.0), (900.0, 1800.0)]
+        );
+
+        // Overlapping segments and segments beyond the end of the video
+        assert_eq!(
+            keep_intervals(&[(700.0, 900.0), (600.0, 800.0), (1700.0, 2000.0)], 1800.0),
+            vec![(0.0, 600.0), (900.0, 1700.0)]
+        );
+
+        // Segments at the start and at the end of the video
+        assert_eq!(
+            keep_intervals(&[(0.0, 120.0), (2900.0, 3000.0)], 3000.0),
+            vec![(120.0, 2900.0)]
+        );
+
+        assert_eq!(keep_intervals(&[], 90.5), vec![(0.0, 90.5)]);
+        assert!(keep_intervals(&[(0.0, 1800.0)], 1800.0).is_empty());
+    }
+
+    #[test]
+    fn time_string_has_microseconds() {
+        assert_eq!(time_string(0.0), "0:00:00.000000");
+        assert_eq!(time_string(3723.0000004), "1:02:03.000000");
+        assert_eq!(time_string(36000.25), "10:00:00.250000");
+    }
+}
@@ -4,24 +4,237 @@
 
 mod cfg;
 mod cli;
+mod crash;
+mod cutlist;
+mod deps;
+mod detect;
+mod doctor;
+mod edl;
+mod notify;
+mod probe;
+mod proctitle;
+mod schedule;
+mod setup;
 mod video;
 
-use crate::video::Video;
+use crate::{
+    notify::{Event, EventKind, Notifiers},
+    video::{ProcessingError, Stage, Video},
+};
 use anyhow::anyhow;
+use chrono::Local;
 use itertools::Itertools;
 use log::*;
+use otr_utils::cutting::{CutlistAccessType, CutlistCtrl};
 use regex::Regex;
+use std::time::Instant;
+use std::{collections::HashSet, env, ffi::OsString, path::Path, time::Duration};
+
+/// Exit code if all videos were processed successfully
+const EXIT_CODE_OK: i32 = 0;
+/// Exit code if an error occurred during the processing of videos
+const EXIT_CODE_ERROR: i32 = 1;
+/// Exit code if the processing of some videos failed, while the other videos
+/// were processed successfully
+const EXIT_CODE_PARTIAL_SUCCESS: i32 = 2;
+/// Exit code if videos could not be cut since there were no cut lists for them
+const EXIT_CODE_NO_CUTLIST: i32 = 3;
+/// Exit code if videos could not be decoded since the OTR access data (user,
+/// password) is missing. Access data that is rejected by OTR is not covered,
+/// since otr-utils does not report that as specific error
+const EXIT_CODE_NO_ACCESS_DATA: i32 = 4;
+/// Exit code if external tools that are required are missing
+const EXIT_CODE_MISSING_TOOLS: i32 = 5;
+
+/// Options that control the processing of videos. They are assembled once from
+/// the command line parameters and the configuration file, and passed
+/// explicitly to the processing functions
+struct Options {
+    /// Videos (file paths) submitted as command line parameters
+    videos: Vec<&'static Path>,
+    /// Whether videos shall be decoded
+    decode: bool,
+    /// OTR access data (user, password)
+    otr_access_data: Option<(&'static str, &'static str)>,
+    /// Whether videos shall be cut
+    cut: bool,
+    /// Attributes that control the retrieval, selection and submission of cut
+    /// lists
+    cutlist_ctrl: CutlistCtrl<'static>,
+    /// Cut list from an EDL file, converted into time intervals. If it is set,
+    /// it replaces the access type of cutlist_ctrl
+    edl_cutlist: Option<String>,
+    /// Whether cut videos shall be verified
+    verify: bool,
+    /// Threshold above which a constant A/V offset of decoded videos is
+    /// corrected before cutting
+    av_sync_threshold: Option<Duration>,
+    /// Whether to wait if another instance of otr is processing videos
+    wait: bool,
+    /// Settings for uploading cut videos
+    upload: Option<&'static cfg::Upload>,
+    /// Backends that send notifications about processed videos
+    notifiers: Notifiers,
+    /// Whether videos that were processed already shall be reported
+    report_processed: bool,
+    /// Processing stage that shall fail deliberately (to test error handling)
+    fail_at: Option<Stage>,
+    /// Maximum time that the processing of a single video may take. If it is
+    /// set, each video is processed in a child process
+    per_video_timeout: Option<Duration>,
+    /// Command line arguments for the child process that processes a single
+    /// video
+    child_args: Vec<OsString>,
+    /// Environment variables for the child process that processes a single
+    /// video
+    child_env: Vec<(&'static str, &'static str)>,
+    /// Time after which the cutting of videos is retried for which there was
+    /// no cut list
+    retry_after: Option<Duration>,
+    /// Whether a report about the processed videos shall be written
+    report: bool,
+    /// Whether otr runs as child process that processes a single video
+    is_child: bool,
+}
+
+impl Options {
+    /// Assemble options from command line parameters and configuration file.
+    /// Command line parameters take precedence over the configuration
+    fn new() -> anyhow::Result<Self> {
+        let videos = cli::videos();
+
+        let cutlist_ctrl = CutlistCtrl {
+            submit: cfg::submit_cutlists(),
+            rating: cli::cutlist_rating().unwrap_or_else(cfg::cutlist_rating),
+            min_rating: cli::min_cutlist_rating().or_else(cfg::min_cutlist_rating),
+            access_token: cfg::cutlist_at_access_token(),
+            access_type: cli::cutlist_access_type(),
+        };
+        let decode = cli::is_decode_command()
+            || ((cli::is_process_command() || cli::is_watch_command())
+                && is_in_window("Decoding", cfg::decoding_window()));
+
+        Ok(Options {
+            videos,
+            decode,
+            // The access data is only determined if videos are decoded, since
+            // warnings are logged if it is not maintained
+            otr_access_data: if decode {
+                cli::otr_access_data().or_else(cfg::otr_access_data)
+            } else {
+                None
+            },
+            cut: cli::is_cut_command()
+                || ((cli::is_process_command() || cli::is_watch_command())
+                    && is_in_window("Cutting", cfg::cutting_window())),
+            cutlist_ctrl,
+            edl_cutlist: None,
+            verify: !cli::no_verify(),
+            av_sync_threshold: cfg::av_sync_threshold(),
+            wait: cli::wait() || cli::is_watch_command(),
+            upload: cfg::upload(),
+            notifiers: Notifiers::from_cfg(),
+            report_processed: cli::is_process_command(),
+            fail_at: cli::fail_at(),
+            per_video_timeout: cli::per_video_timeout(),
+            child_args: cli::process_args_for_child(),
+            child_env: cli::process_env_for_child(),
+            retry_after: cli::retry_after(),
+            report: cli::report(),
+            is_child: cli::is_child(),
+        })
+    }
+
+    /// Converts the cut list from an EDL file into time intervals, if such a
+    /// file was submitted. Since ffprobe is required for that, this must only be
+    /// done after the tools were checked
+    fn convert_edl_cutlist(&mut self) -> anyhow::Result<()> {
+        if let Some(edl) = cli::cutlist_edl() {
+            if self.videos.len() != 1 {
+                return Err(anyhow!(
+                    "A cut list from an EDL file can only be applied to one video"
+                ));
+            }
+            self.edl_cutlist = Some(edl::intervals_from_edl(edl, self.videos[0])?);
+        }
+
+        Ok(())
+    }
+
+    /// Attributes that control the retrieval, selection and submission of cut
+    /// lists, with the cut list from an EDL file as access type if there is one
+    fn cutlist_ctrl(&self) -> CutlistCtrl<'_> {
+        let access_type = match (&self.edl_cutlist, &self.cutlist_ctrl.access_type) {
+            (Some(intervals), _) => CutlistAccessType::Direct(intervals),
+            (None, CutlistAccessType::Auto) => CutlistAccessType::Auto,
+            (None, CutlistAccessType::Direct(intervals)) => CutlistAccessType::Direct(intervals),
+            (None, CutlistAccessType::File(file)) => CutlistAccessType::File(file),
+            (None, CutlistAccessType::ID(id)) => CutlistAccessType::ID(*id),
+        };
+
+        CutlistCtrl {
+            submit: self.cutlist_ctrl.submit,
+            rating: self.cutlist_ctrl.rating,
+            min_rating: self.cutlist_ctrl.min_rating,
+            access_token: self.cutlist_ctrl.access_token,
+            access_type,
+        }
+    }
+}
+
+/// Checks if the current time is inside the time window that is configured for
+/// a processing stage. If no window is configured, the stage can be executed at
+/// any time. Videos of a stage that is outside of its window remain in their
+/// working sub directory and are processed in a later run
+fn is_in_window(stage: &str, window: Option<schedule::TimeWindow>) -> bool {
+    match window {
+        Some(_window) if !_window.contains_now() => {
+            info!(
+                "{} is deferred since the current time is outside of the configured time window {}",
+                stage, _window
+            );
+            false
+        }
+        _ => true,
+    }
+}
 
 /// Process videos (i.e., collect, move, decode and cut them). This is done in a
 /// dedicated function (with appropriate result type) to be able to use the ?
-/// operator to propagate errors
-fn process_videos() -> anyhow::Result<()> {
+/// operator to propagate errors. Errors of single videos do not stop the
+/// processing of the others. They are reported, and the exit code is derived
+/// from them. Encoded videos whose file names are contained in changing are
+/// skipped
+fn process_videos(opts: &Options, changing: &HashSet<OsString>) -> anyhow::Result<i32> {
+    // Overlapping runs of otr (e.g., by cron and manually) would race on the
+    // same video files. The lock is released when it goes out of scope
+    let _lock = video::Lock::acquire(opts.wait)?;
+
+    // If videos are processed in child processes, the retry queue is managed
+    // by the parent process, since the children report missing cut lists via
+    // their exit code
+    let mut retry_queue = if opts.retry_after.is_some() {
+        Some(video::RetryQueue::load()?)
+    } else {
+        None
+    };
+
     // Collect video files from command line parameters and (sub) working
     // directories. They are returned as vector sorted by video key and
     // (descending) status.
-    #[allow(clippy::manual_try_fold)]
+    let mut videos = video::collect(&opts.videos, changing)?;
+    let mut report = video::Report::new();
+
+    // Number of videos (without duplicates) and number of the current video for
+    // the process title
+    let total = videos
+        .iter()
+        .dedup_by(|v1, v2| v1.key() == v2.key())
+        .count();
+    let mut count = 0;
+
     #[allow(clippy::manual_inspect)]
-    video::collect(&cli::videos())?
+    let videos = videos
         // Create an iterator that delivers type &mut Video
         .iter_mut()
         // Remove duplicate entries of the same video with "lower" status.
@@ -32,47 +245,225 @@ fn process_videos() -> anyhow::Result<()> {
         .dedup_by(|v1, v2| v1.key() == v2.key())
         // Print message for already cut videos
         .map(|video| {
-            if cli::is_process_command() && video.is_processed() {
+            if opts.report_processed && video.is_processed() {
                 info!("Processed already: \"{}\"", video.file_name());
             }
             video
         })
-        // Decode videos and print error messages. Result of the closure is the
-        // video (&mut Video), whether the decoding was successful or not. Errors
-        // are collected in an attribute of the video structure
+        // Decode and cut videos and print error messages. Result of the
+        // closure is the video (&mut Video), whether the decoding and cutting
+        // was successful or not. Errors are collected in an attribute of the
+        // video structure
         .map(|video| {
-            if cli::is_decode_command() || cli::is_process_command() {
-                video.decode(cli::otr_access_data());
+            count += 1;
+            let (started, status) = (Instant::now(), video.status());
+            // Skip videos for which there was no cut list recently
+            if let (Some(queue), Some(retry_after)) = (&retry_queue, opts.retry_after) {
+                if let Some(failed_at) = queue.failed_recently(video, retry_after) {
+                    info!(
+                        "Skipped \"{}\": There was no cut list at {}",
+                        video.file_name(),
+                        failed_at.format("%Y-%m-%d %H:%M")
+                    );
+                    return video;
+                }
             }
-            video
-        })
-        .map(|video| {
-            if cli::is_cut_command() || cli::is_process_command() {
-                video.cut(
-                    cli::cutlist_access_type(),
-                    if cli::is_cut_command() {
-                        cli::cutlist_rating()
-                    } else {
-                        None
-                    },
-                    cli::min_cutlist_rating(),
+            // If the processing time per video is limited, the video is
+            // processed in a child process that can be killed if necessary
+            if let Some(timeout) = opts.per_video_timeout {
+                video.process_with_timeout(
+                    &opts.child_args,
+                    &opts.child_env,
+                    timeout,
+                    processing_error,
                 );
+                if let Some(queue) = &mut retry_queue {
+                    queue.update(video);
+                }
+                report.add(video, status, started.elapsed());
+                return video;
+            }
+            if opts.decode {
+                proctitle::set(&format!("otr decode {}/{}", count, total));
+                if opts.fail_at == Some(Stage::Decode) {
+                    video.fail(Stage::Decode);
+                } else {
+                    video.decode(opts.otr_access_data);
+                }
+            }
+            if opts.cut {
+                proctitle::set(&format!("otr cut {}/{}", count, total));
+                let status = video.status();
+                if opts.fail_at == Some(Stage::Cut) {
+                    video.fail(Stage::Cut);
+                } else {
+                    video.cut(&opts.cutlist_ctrl(), opts.verify, opts.av_sync_threshold);
+                }
+                // Upload videos that were cut in this run
+                if status != video.status() {
+                    opts.notifiers.notify(&Event::new(
+                        EventKind::Cut,
+                        format!("Cut \"{}\"", video.file_name()),
+                    ));
+                    if let Some(upload) = opts.upload {
+                        video.upload(upload);
+                    }
+                }
             }
+            if let Some(queue) = &mut retry_queue {
+                queue.update(video);
+            }
+            report.add(video, status, started.elapsed());
             video
         })
         // Collect videos the parallel cut step
-        .collect::<Vec<&mut Video>>()
-        // Handle errors that occured during decoding or cutting. Because of the
-        // collect step before, this is the standard fold and not the rayon fold
+        .collect::<Vec<&mut Video>>();
+
+    proctitle::set("otr");
+    if let Some(queue) = &retry_queue {
+        queue.save()?;
+    }
+    if opts.report {
+        match report.write() {
+            Ok(path) => info!("Report written to \"{}\"", path.display()),
+            Err(err) => warn!("{:?}", err),
+        }
+    }
+
+    // Send a summary of the run. If videos are processed in child processes,
+    // this is only done by the parent process
+    if !opts.is_child {
+        if let Some(summary) = report.summary() {
+            opts.notifiers
+                .notify(&Event::new(EventKind::Summary, summary));
+        }
+    }
+
+    // Handle errors that occured during decoding or cutting
+    let failed: Vec<&Video> = videos
         .iter()
-        .fold(Ok(()), |res, video| {
+        .map(|video| &**video)
+        .filter(|video| {
+            // If cutting is retried later, a missing cut list is no error
+            if opts.retry_after.is_some() && video.lacks_cutlist() {
+                warn!(
+                    "\"{}\": No cut list exists yet. Cutting is retried later",
+                    video.file_name()
+                );
+                return false;
+            }
             if let Some(err) = video.error() {
                 error!("\"{}\":\n{:?}\n", video.file_name(), err);
-                Err(anyhow!("An error occurred during processing of OTR videos"))
+                // If videos are processed in child processes, the parent
+                // process sends the notifications about failures, since only
+                // it knows whether cutting is retried later
+                if !opts.is_child {
+                    opts.notifiers.notify(&Event::new(
+                        EventKind::Failed,
+                        format!("Could not process \"{}\": {:#}", video.file_name(), err),
+                    ));
+                }
+                true
             } else {
-                res
+                false
             }
         })
+        .collect();
+
+    Ok(exit_code(&failed, videos.len()))
+}
+
+/// Derives the exit code from the videos whose processing failed (total is the
+/// number of all videos). If failures are mixed, this precedence applies:
+/// 1. If OTR access data was missing for at least one video, the dedicated
+///    exit code for that is returned, since that must be fixed by the user
+/// 2. If all videos failed since there were no cut lists for them (which is
+///    typically resolved over time), the dedicated exit code for that is
+///    returned
+/// 3. Otherwise, the exit code tells whether other videos were processed
+///    successfully
+fn exit_code(failed: &[&Video], total: usize) -> i32 {
+    if failed.is_empty() {
+        return EXIT_CODE_OK;
+    }
+
+    let failed_with = |is_reason: fn(&ProcessingError) -> bool| {
+        failed
+            .iter()
+            .map(move |video| video.processing_error().is_some_and(is_reason))
+    };
+    if failed_with(|err| matches!(err, ProcessingError::NoAccessData)).any(|is_reason| is_reason) {
+        return EXIT_CODE_NO_ACCESS_DATA;
+    }
+    if failed_with(|err| matches!(err, ProcessingError::NoCutlist)).all(|is_reason| is_reason) {
+        return EXIT_CODE_NO_CUTLIST;
+    }
+
+    if failed.len() < total {
+        EXIT_CODE_PARTIAL_SUCCESS
+    } else {
+        EXIT_CODE_ERROR
+    }
+}
+
+/// Maps the exit code of a child process that processed a single video (see
+/// option --per-video-timeout) to the processing error that caused it. That's
+/// the reverse of exit_code() for a single video. For exit codes without a
+/// dedicated processing error, None is returned
+fn processing_error(exit_code: i32) -> Option<ProcessingError> {
+    match exit_code {
+        EXIT_CODE_NO_CUTLIST => Some(ProcessingError::NoCutlist),
+        EXIT_CODE_NO_ACCESS_DATA => Some(ProcessingError::NoAccessData),
+        _ => None,
+    }
+}
+
+/// Checks if the external tools that are required for cutting (if cut is true,
+/// incl. uploading if upload is true) and for sending notifications are
+/// installed. If not, otr is terminated
+fn check_tools(cut: bool, upload: bool, notifiers: &Notifiers) {
+    let mut tools: Vec<&str> = vec![];
+    if cut {
+        tools.extend(deps::CUTTING_TOOLS);
+        if upload {
+            tools.push(video::UPLOAD_TOOL);
+        }
+    }
+    tools.extend(notifiers.tools());
+    if let Err(err) = deps::check(&tools) {
+        error!("{}", err);
+        std::process::exit(EXIT_CODE_MISSING_TOOLS);
+    }
+}
+
+/// Processes videos whenever new encoded videos appear, the retry interval
+/// elapsed, or the time window for decoding or cutting opened (so that videos
+/// that were deferred before are processed). The options are assembled again
+/// for each run, since the time windows might have been entered or left in the
+/// meantime. Errors are reported, but do not stop watching
+fn watch_videos(debounce: Duration, retry_interval: Duration) -> ! {
+    check_tools(true, cfg::upload().is_some(), &Notifiers::from_cfg());
+
+    let windows: Vec<schedule::TimeWindow> = [cfg::decoding_window(), cfg::cutting_window()]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    info!("Watching for new videos ...");
+    let mut watcher = video::Watcher::new(debounce, retry_interval);
+    let mut last_run = Local::now().naive_local();
+    loop {
+        let changing = watcher.wait(|| windows.iter().any(|window| window.opened_since(last_run)));
+        last_run = Local::now().naive_local();
+        match Options::new() {
+            Ok(opts) => {
+                if let Err(err) = process_videos(&opts, &changing) {
+                    error!("{:?}", err);
+                }
+            }
+            Err(err) => error!("{:?}", err),
+        }
+    }
 }
 
 fn main() {
@@ -95,8 +486,172 @@ fn main() {
         // Provoke dump in case of an error
         .unwrap();
 
+    // Write a crash report if otr panics (opt-in)
+    if cfg::crash_reports() {
+        crash::install_reporter();
+    }
+
+    // Proxy settings from the configuration file take precedence over the
+    // corresponding environment variables. They must be set before any HTTP
+    // request is done (and before any thread is spawned)
+    for (var, value) in cfg::proxy_env_vars() {
+        debug!("Using proxy setting {}={} from configuration", var, value);
+        env::set_var(var, value);
+    }
+
+    // External tools (e.g., ffmpeg) are executed as child processes, which
+    // inherit the environment of otr. Thus, the environment variables that are
+    // configured for them are set for otr. This must be done before any thread
+    // is spawned
+    for (var, value) in cfg::tool_env_vars() {
+        debug!("Setting {}={} for external tools", var, value);
+        env::set_var(var, value);
+    }
+
+    // otr-utils stores temporary files in the cache directory of the user, which
+    // is determined from XDG_CACHE_HOME on Linux. Thus, a different directory
+    // for temporary files can be set via that environment variable. This must
+    // be done before any thread is spawned
+    #[cfg(target_os = "linux")]
+    if let Some(tmp_dir) = cli::tmp_dir() {
+        match std::path::absolute(tmp_dir) {
+            Ok(_tmp_dir) => {
+                debug!(
+                    "Using directory \"{}\" for temporary files",
+                    _tmp_dir.display()
+                );
+                env::set_var("XDG_CACHE_HOME", _tmp_dir);
+            }
+            Err(err) => {
+                error!(
+                    "{:?}",
+                    anyhow!(err).context(format!(
+                        "Could not determine directory for temporary files \"{}\"",
+                        tmp_dir.display()
+                    ))
+                );
+                std::process::exit(EXIT_CODE_ERROR);
+            }
+        }
+    }
+
+    // Remove temporary and stale artefacts
+    if cli::is_clean_command() {
+        if let Err(err) = video::clean(cli::dry_run()) {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+        return;
+    }
+
+    // Manage archive of decoded videos
+    if let Some(action) = cli::archive_action() {
+        if let Err(err) = video::archive(action, cli::tag_filter()) {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+        return;
+    }
+
+    // Copy cut videos to external storage
+    if let Some((dest, filter, newer_than)) = cli::export_params() {
+        if let Err(err) = video::export(dest, filter, newer_than, cli::tag_filter()) {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+        return;
+    }
+
+    // Detect commercial breaks and create a cut list
+    if let Some((video, cutlist)) = cli::detect_cuts_params() {
+        if let Err(err) = detect::detect_cuts(video, &cutlist) {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+        return;
+    }
+
+    // Add, remove or list tags of a video
+    if let Some((video, tags, remove)) = cli::tag_params() {
+        if let Err(err) = video::tag(video, tags, remove) {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+        return;
+    }
+
+    // Remove personal data and caches
+    if let Some((credentials, caches, state)) = cli::purge_params() {
+        if let Err(err) = video::purge(credentials, caches, state) {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+        return;
+    }
+
+    // Write default configuration
+    if let Some(force) = cli::setup_defaults() {
+        if let Err(err) = setup::write_defaults(force) {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+        return;
+    }
+
+    // Check the setup of otr
+    if cli::is_doctor_command() {
+        if let Err(err) = doctor::run() {
+            error!("{}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+        return;
+    }
+
+    // Process new videos automatically
+    if let Some((debounce, retry_interval)) = cli::watch_params() {
+        watch_videos(debounce, retry_interval);
+    }
+
+    let mut opts = match Options::new() {
+        Ok(_opts) => _opts,
+        Err(err) => {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+    };
+
+    // Check up front if the external tools that are required for cutting and
+    // for notifications are installed, instead of failing during the first
+    // attempt
+    check_tools(opts.cut, opts.upload.is_some(), &opts.notifiers);
+
+    if let Err(err) = opts.convert_edl_cutlist() {
+        error!("{:?}", err);
+        std::process::exit(EXIT_CODE_ERROR);
+    }
+
+    // Cut a video outside of the working directories
+    if let Some(out) = cli::in_place_out() {
+        let res = match opts.videos[..] {
+            [video] => video::cut_in_place(video, out, &opts.cutlist_ctrl(), opts.verify),
+            _ => Err(anyhow!(
+                "--in-place-out requires that only one video is submitted"
+            )),
+        };
+        if let Err(err) = res {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
+        return;
+    }
+
     // Process video files (collect, decode and cut them)
-    if process_videos().is_err() {
-        std::process::exit(1);
+    match process_videos(&opts, &HashSet::new()) {
+        Ok(EXIT_CODE_OK) => (),
+        Ok(exit_code) => std::process::exit(exit_code),
+        Err(err) => {
+            error!("{:?}", err);
+            std::process::exit(EXIT_CODE_ERROR);
+        }
     }
 }
@@ -0,0 +1,29 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{execute, Event, Notifier};
+
+use std::process::Command;
+
+/// Tool that desktop notifications are sent with
+const NOTIFY_SEND: &str = "notify-send";
+
+/// Desktop notifications (via notify-send)
+pub struct Desktop;
+
+impl Notifier for Desktop {
+    fn notify(&self, event: &Event) -> anyhow::Result<()> {
+        execute(
+            Command::new(NOTIFY_SEND)
+                .args(["--app-name", "otr"])
+                .arg(&event.title)
+                .arg(&event.message),
+            None,
+        )
+    }
+
+    fn tool(&self) -> &'static str {
+        NOTIFY_SEND
+    }
+}
@@ -0,0 +1,32 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{execute, Event, Notifier};
+
+use std::process::Command;
+
+/// Tool that e-mails are sent with
+const SENDMAIL: &str = "sendmail";
+
+/// E-mail notifications (via the sendmail interface of the local mail transfer
+/// agent)
+pub struct Email {
+    pub to: &'static str,
+}
+
+impl Notifier for Email {
+    fn notify(&self, event: &Event) -> anyhow::Result<()> {
+        execute(
+            Command::new(SENDMAIL).arg("-t"),
+            Some(&format!(
+                "To: {}\nSubject: {}\nContent-Type: text/plain; charset=utf-8\n\n{}\n",
+                self.to, event.title, event.message
+            )),
+        )
+    }
+
+    fn tool(&self) -> &'static str {
+        SENDMAIL
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+mod desktop;
+mod email;
+mod ntfy;
+mod telegram;
+mod webhook;
+
+use crate::cfg::{self, NotificationBackend};
+
+use anyhow::{anyhow, Context};
+use itertools::Itertools;
+use log::*;
+use std::{
+    env, fmt,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Tool that notifications are sent with via HTTP
+const CURL: &str = "curl";
+
+/// Kinds of events that notifications are sent for
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    /// A video was cut
+    Cut,
+    /// The processing of a video failed
+    Failed,
+    /// The processing of all videos finished (sent only if videos were decoded,
+    /// cut or failed)
+    Summary,
+}
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventKind::Cut => write!(f, "Video cut"),
+            EventKind::Failed => write!(f, "Processing failed"),
+            EventKind::Summary => write!(f, "Processing finished"),
+        }
+    }
+}
+
+/// Event that a notification is sent for
+#[derive(serde::Serialize)]
+pub struct Event {
+    kind: EventKind,
+    title: String,
+    message: String,
+}
+impl Event {
+    pub fn new(kind: EventKind, message: String) -> Self {
+        Event {
+            kind,
+            title: format!("otr: {}", kind),
+            message,
+        }
+    }
+}
+
+/// Backend that sends notifications via a certain channel (e.g., desktop or
+/// e-mail). To support a new channel, a type that implements this trait must be
+/// created, and the channel must be added to NotificationBackend (see cfg.rs)
+/// and to the function notifier
+trait Notifier {
+    fn notify(&self, event: &Event) -> anyhow::Result<()>;
+
+    /// External tool the notifications are sent with
+    fn tool(&self) -> &'static str;
+}
+
+/// Notification backend with the kinds of events it sends notifications for
+/// (None means all kinds)
+struct Entry {
+    notifier: Box<dyn Notifier>,
+    events: Option<&'static [EventKind]>,
+}
+
+/// Notification backends that are configured in the configuration file
+pub struct Notifiers(Vec<Entry>);
+
+impl Notifiers {
+    /// Creates the notification backends from the configuration file
+    pub fn from_cfg() -> Self {
+        Notifiers(
+            cfg::notifications()
+                .iter()
+                .map(|notification| Entry {
+                    notifier: notifier(notification.backend()),
+                    events: notification.events(),
+                })
+                .collect(),
+        )
+    }
+
+    /// External tools that are required to send the notifications
+    pub fn tools(&self) -> Vec<&'static str> {
+        self.0
+            .iter()
+            .map(|entry| entry.notifier.tool())
+            .unique()
+            .collect()
+    }
+
+    /// Sends a notification about an event via all backends that are
+    /// configured for its kind. Errors are only reported
+    pub fn notify(&self, event: &Event) {
+        for entry in &self.0 {
+            if entry
+                .events
+                .is_some_and(|events| !events.contains(&event.kind))
+            {
+                continue;
+            }
+            if let Err(err) = entry.notifier.notify(event) {
+                warn!("{:?}", err.context("Could not send notification"));
+            }
+        }
+    }
+}
+
+/// Creates the notifier for a backend from the configuration file
+fn notifier(backend: &'static NotificationBackend) -> Box<dyn Notifier> {
+    match backend {
+        NotificationBackend::Desktop => Box::new(desktop::Desktop),
+        NotificationBackend::Email { to } => Box::new(email::Email { to }),
+        NotificationBackend::Ntfy { server, topic } => Box::new(ntfy::Ntfy {
+            server: server.as_deref(),
+            topic,
+        }),
+        NotificationBackend::Telegram { bot_token, chat_id } => {
+            Box::new(telegram::Telegram { bot_token, chat_id })
+        }
+        NotificationBackend::Webhook { url } => Box::new(webhook::Webhook { url }),
+    }
+}
+
+/// Command to send a notification via HTTP with curl. For security reasons,
+/// curl ignores the environment variable HTTP_PROXY in upper case, which otr
+/// sets for the proxy from the configuration. Thus, its value is passed as
+/// http_proxy, unless that is set already
+fn curl() -> Command {
+    let mut cmd = Command::new(CURL);
+    cmd.args(["--silent", "--show-error", "--fail"]);
+    if let (Some(proxy), None) = (env::var_os("HTTP_PROXY"), env::var_os("http_proxy")) {
+        cmd.env("http_proxy", proxy);
+    }
+    cmd
+}
+
+/// Executes the external tool of a notification backend. If input is given, it
+/// is passed to the tool via stdin
+fn execute(cmd: &mut Command, input: Option<&str>) -> anyhow::Result<()> {
+    let tool = cmd.get_program().to_string_lossy().into_owned();
+
+    let mut child = cmd
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Could not execute {}", tool))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .with_context(|| format!("Could not pass data to {}", tool))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Could not wait for {}", tool))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed: {}",
+            tool,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
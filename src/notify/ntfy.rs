@@ -0,0 +1,34 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{curl, execute, Event, Notifier, CURL};
+
+/// ntfy server that is used if none is configured
+const DEFAULT_SERVER: &str = "https://ntfy.sh";
+
+/// Push notifications via ntfy (sent with curl)
+pub struct Ntfy {
+    pub server: Option<&'static str>,
+    pub topic: &'static str,
+}
+
+impl Notifier for Ntfy {
+    fn notify(&self, event: &Event) -> anyhow::Result<()> {
+        execute(
+            curl()
+                .args(["--header", &format!("Title: {}", event.title)])
+                .args(["--data-binary", &event.message])
+                .arg(format!(
+                    "{}/{}",
+                    self.server.unwrap_or(DEFAULT_SERVER).trim_end_matches('/'),
+                    self.topic
+                )),
+            None,
+        )
+    }
+
+    fn tool(&self) -> &'static str {
+        CURL
+    }
+}
@@ -0,0 +1,35 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{curl, execute, Event, Notifier, CURL};
+
+/// Telegram messages via a bot (sent with curl)
+pub struct Telegram {
+    pub bot_token: &'static str,
+    pub chat_id: &'static str,
+}
+
+impl Notifier for Telegram {
+    fn notify(&self, event: &Event) -> anyhow::Result<()> {
+        execute(
+            curl()
+                .args(["--data-urlencode", &format!("chat_id={}", self.chat_id)])
+                .args([
+                    "--data-urlencode",
+                    &format!("text={}\n{}", event.title, event.message),
+                ])
+                // The URL contains the bot token. It is passed via stdin to not
+                // expose the token in the process list
+                .args(["--config", "-"]),
+            Some(&format!(
+                "url = \"https://api.telegram.org/bot{}/sendMessage\"\n",
+                self.bot_token
+            )),
+        )
+    }
+
+    fn tool(&self) -> &'static str {
+        CURL
+    }
+}
@@ -0,0 +1,31 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{curl, execute, Event, Notifier, CURL};
+
+use anyhow::Context;
+
+/// Webhooks: The event is posted as JSON document (with curl)
+pub struct Webhook {
+    pub url: &'static str,
+}
+
+impl Notifier for Webhook {
+    fn notify(&self, event: &Event) -> anyhow::Result<()> {
+        execute(
+            curl()
+                .args(["--header", "Content-Type: application/json"])
+                .args([
+                    "--data-binary",
+                    &serde_json::to_string(event).context("Could not serialize event")?,
+                ])
+                .arg(self.url),
+            None,
+        )
+    }
+
+    fn tool(&self) -> &'static str {
+        CURL
+    }
+}
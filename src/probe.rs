@@ -0,0 +1,146 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::{anyhow, Context};
+use std::{path::Path, process::Command};
+
+/// Tolerance (in seconds) for the comparison of the durations of a cut video
+/// and its source. For the comparison with the kept intervals, it applies per
+/// interval, since each cut can shift the boundaries slightly
+const DURATION_TOLERANCE: f64 = 1.0;
+
+/// Verifies the result of cutting: The cut video must contain the same number
+/// of video and audio streams as its source. If the kept intervals are known,
+/// the duration of the cut video must equal their total length. Otherwise, it
+/// must be greater than zero and not exceed the duration of the source
+pub fn verify_cut(source: &Path, cut: &Path, kept: Option<&[(f64, f64)]>) -> anyhow::Result<()> {
+    let source_streams = stream_counts(source)?;
+    let cut_streams = stream_counts(cut)?;
+    if cut_streams != source_streams {
+        return Err(anyhow!(
+            "Cut video has {} video and {} audio stream(s), but source has {} and {}",
+            cut_streams.0,
+            cut_streams.1,
+            source_streams.0,
+            source_streams.1
+        ));
+    }
+
+    let cut_duration = duration(cut)?;
+    if let Some(kept) = kept {
+        let kept_duration: f64 = kept.iter().map(|(start, end)| end - start).sum();
+        if (cut_duration - kept_duration).abs() > DURATION_TOLERANCE * kept.len() as f64 {
+            return Err(anyhow!(
+                "Duration of cut video ({:.1}s) does not match the total length of the kept intervals ({:.1}s)",
+                cut_duration,
+                kept_duration
+            ));
+        }
+        return Ok(());
+    }
+
+    let source_duration = duration(source)?;
+    if cut_duration <= 0.0 || cut_duration > source_duration + DURATION_TOLERANCE {
+        return Err(anyhow!(
+            "Duration of cut video ({:.1}s) is not plausible (duration of source: {:.1}s)",
+            cut_duration,
+            source_duration
+        ));
+    }
+
+    Ok(())
+}
+
+/// Duration of a video in seconds, determined with ffprobe
+pub fn duration(video: &Path) -> anyhow::Result<f64> {
+    ffprobe(video, &["-show_entries", "format=duration"])?
+        .trim()
+        .parse::<f64>()
+        .with_context(|| format!("Could not determine duration of \"{}\"", video.display()))
+}
+
+/// Frame rate (frames per second) of the first video stream of a video,
+/// determined with ffprobe
+pub fn frame_rate(video: &Path) -> anyhow::Result<f64> {
+    let frame_rate = ffprobe(
+        video,
+        &[
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=avg_frame_rate",
+        ],
+    )?;
+    let frame_rate = frame_rate.trim();
+
+    // ffprobe returns the frame rate as fraction, e.g. "25/1"
+    frame_rate
+        .split_once('/')
+        .map_or_else(
+            || frame_rate.parse::<f64>().ok(),
+            |(num, den)| Some(num.parse::<f64>().ok()? / den.parse::<f64>().ok()?),
+        )
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .ok_or_else(|| anyhow!("Could not determine frame rate of \"{}\"", video.display()))
+}
+
+/// Offset (in seconds) between audio and video of a video, i.e. the difference
+/// of the start times of the first audio and the first video stream, determined
+/// with ffprobe. A positive offset means that the audio starts later than the
+/// video. If the video has no audio or no video stream, None is returned
+pub fn av_offset(video: &Path) -> anyhow::Result<Option<f64>> {
+    let start_time = |stream: &str| -> anyhow::Result<Option<f64>> {
+        Ok(ffprobe(
+            video,
+            &[
+                "-select_streams",
+                stream,
+                "-show_entries",
+                "stream=start_time",
+            ],
+        )?
+        .lines()
+        .next()
+        .and_then(|line| line.trim().parse::<f64>().ok()))
+    };
+
+    match (start_time("a:0")?, start_time("v:0")?) {
+        (Some(audio), Some(video)) => Ok(Some(audio - video)),
+        _ => Ok(None),
+    }
+}
+
+/// Numbers of video and audio streams of a video, determined with ffprobe
+fn stream_counts(video: &Path) -> anyhow::Result<(usize, usize)> {
+    let codec_types = ffprobe(video, &["-show_entries", "stream=codec_type"])?;
+    let count = |codec_type: &str| {
+        codec_types
+            .lines()
+            .filter(|line| line.trim() == codec_type)
+            .count()
+    };
+
+    Ok((count("video"), count("audio")))
+}
+
+/// Executes ffprobe with the given arguments for a video and returns its output
+/// (values only, one per line)
+fn ffprobe(video: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error"])
+        .args(args)
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(video)
+        .output()
+        .context("Could not execute ffprobe")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Could not analyze \"{}\": {}",
+            video.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
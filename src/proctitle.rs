@@ -0,0 +1,20 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+#[cfg(target_os = "linux")]
+use std::ffi::CString;
+
+/// Sets the name of the otr process as it is displayed by ps or top (e.g., "otr
+/// cut 3/12"), so that it is visible what a long-running otr process is doing.
+/// Linux truncates the name to 15 bytes. On other operating systems, the name is
+/// not changed
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+pub fn set(title: &str) {
+    #[cfg(target_os = "linux")]
+    if let Ok(title) = CString::new(title) {
+        unsafe {
+            libc::prctl(libc::PR_SET_NAME, title.as_ptr() as libc::c_ulong, 0, 0, 0);
+        }
+    }
+}
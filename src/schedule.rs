@@ -0,0 +1,208 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::anyhow;
+use chrono::{Duration, Local, NaiveDateTime, Timelike};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{fmt, str::FromStr};
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+lazy_static! {
+    // Time window in the format "hh:mm-hh:mm"
+    static ref RE_TIME_WINDOW: Regex =
+        Regex::new(r"^(?P<fh>\d{1,2}):(?P<fm>\d{2})-(?P<th>\d{1,2}):(?P<tm>\d{2})$").unwrap();
+}
+
+/// Time window of a day, such as 00:00-07:00. Start and end are stored as
+/// minutes since midnight. The start belongs to the window, the end does not.
+/// If the end is before the start (e.g., 22:00-06:00), the window spans
+/// midnight. Start and end must differ - the whole day is 00:00-24:00
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeWindow {
+    from: u16,
+    to: u16,
+}
+/// Support conversion of strings such as "00:00-07:00" into TimeWindow
+impl FromStr for TimeWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let captures = RE_TIME_WINDOW
+            .captures(s.trim())
+            .ok_or_else(|| anyhow!("\"{}\" is not a valid time window (hh:mm-hh:mm)", s))?;
+
+        let minutes = |h: &str, m: &str| -> anyhow::Result<u16> {
+            let (h, m) = (
+                captures[h].parse::<u16>().unwrap(),
+                captures[m].parse::<u16>().unwrap(),
+            );
+            if m >= 60 || h * 60 + m > MINUTES_PER_DAY {
+                return Err(anyhow!(
+                    "\"{}\" is not a valid time window: invalid time",
+                    s
+                ));
+            }
+            Ok(h * 60 + m)
+        };
+
+        let (from, to) = (minutes("fh", "fm")?, minutes("th", "tm")?);
+        if from % MINUTES_PER_DAY == to % MINUTES_PER_DAY && (from, to) != (0, MINUTES_PER_DAY) {
+            return Err(anyhow!(
+                "\"{}\" is not a valid time window: start and end are equal (the whole day is 00:00-24:00)",
+                s
+            ));
+        }
+
+        Ok(TimeWindow { from, to })
+    }
+}
+/// Support the use of "{}" format specifier
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.from / 60,
+            self.from % 60,
+            self.to / 60,
+            self.to % 60
+        )
+    }
+}
+impl TimeWindow {
+    /// Checks if the current local time is inside the time window
+    pub fn contains_now(&self) -> bool {
+        self.contains(minutes_since_midnight(&Local::now().naive_local()))
+    }
+
+    /// Checks if the time window opened since the given point in time (local
+    /// time) and is still open
+    pub fn opened_since(&self, since: NaiveDateTime) -> bool {
+        self.opened_between(since, Local::now().naive_local())
+    }
+
+    /// Checks if a time (given as minutes since midnight) is inside the time
+    /// window
+    fn contains(&self, minutes: u16) -> bool {
+        if self.from <= self.to {
+            self.from <= minutes && minutes < self.to
+        } else {
+            minutes >= self.from || minutes < self.to
+        }
+    }
+
+    /// Checks if the time window opened after from and is still open at to. A
+    /// window that covers the whole day never opens
+    fn opened_between(&self, from: NaiveDateTime, to: NaiveDateTime) -> bool {
+        if (self.from, self.to) == (0, MINUTES_PER_DAY)
+            || !self.contains(minutes_since_midnight(&to))
+        {
+            return false;
+        }
+
+        // Latest start of the window that is not after to
+        let start = to
+            .date()
+            .and_hms_opt(
+                (self.from % MINUTES_PER_DAY / 60) as u32,
+                (self.from % 60) as u32,
+                0,
+            )
+            .unwrap();
+        let start = if start > to {
+            start - Duration::days(1)
+        } else {
+            start
+        };
+
+        start > from
+    }
+}
+
+/// Time of a point in time as minutes since midnight
+fn minutes_since_midnight(time: &NaiveDateTime) -> u16 {
+    (time.hour() * 60 + time.minute()) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(s: &str) -> TimeWindow {
+        s.parse::<TimeWindow>().unwrap()
+    }
+
+    fn time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn time_windows_are_parsed() {
+        assert_eq!(window("00:00-07:00"), TimeWindow { from: 0, to: 420 });
+        assert_eq!(
+            window(" 22:30-6:15 "),
+            TimeWindow {
+                from: 1350,
+                to: 375
+            }
+        );
+        assert_eq!(window("00:00-24:00"), TimeWindow { from: 0, to: 1440 });
+        assert_eq!(window("22:00-06:00").to_string(), "22:00-06:00");
+
+        for invalid in [
+            "00:00-00:00",
+            "07:00-07:00",
+            "24:00-00:00",
+            "00:00-24:01",
+            "25:00-07:00",
+            "00:60-07:00",
+            "00:00",
+            "0:0-7:0",
+            "00:00-07:00-08:00",
+        ] {
+            assert!(invalid.parse::<TimeWindow>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn time_windows_contain_minutes() {
+        let night = window("00:00-07:00");
+        assert!(night.contains(0));
+        assert!(night.contains(419));
+        assert!(!night.contains(420));
+
+        let day = window("00:00-24:00");
+        assert!(day.contains(0));
+        assert!(day.contains(1439));
+
+        // Windows that span midnight
+        let night = window("22:00-06:00");
+        assert!(night.contains(1320));
+        assert!(night.contains(1439));
+        assert!(night.contains(0));
+        assert!(night.contains(359));
+        assert!(!night.contains(360));
+        assert!(!night.contains(1319));
+        assert!(!night.contains(720));
+    }
+
+    #[test]
+    fn time_windows_open_between_points_in_time() {
+        let night = window("22:00-06:00");
+        // Window opened in between
+        assert!(night.opened_between(time("2024-01-01 21:00"), time("2024-01-01 22:05")));
+        assert!(night.opened_between(time("2024-01-01 21:00"), time("2024-01-02 01:00")));
+        // Window was open already, or is not open anymore
+        assert!(!night.opened_between(time("2024-01-01 22:30"), time("2024-01-02 01:00")));
+        assert!(!night.opened_between(time("2024-01-01 21:00"), time("2024-01-02 07:00")));
+        // Window closed and opened again in between
+        assert!(night.opened_between(time("2024-01-01 05:00"), time("2024-01-01 23:00")));
+
+        let day = window("00:00-24:00");
+        assert!(!day.opened_between(time("2024-01-01 23:59"), time("2024-01-02 00:01")));
+        assert!(!day.opened_between(time("2024-01-01 00:01"), time("2024-01-01 23:59")));
+    }
+}
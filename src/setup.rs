@@ -0,0 +1,60 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::{cfg, video};
+
+use anyhow::{anyhow, Context};
+use log::*;
+use std::fs;
+
+/// Default configuration that is embedded into the otr binary
+const DEFAULT_CFG: &str = include_str!("../resources/otr.json");
+/// Placeholder for the working directory in the default configuration (incl.
+/// the quotes, since it is replaced by a JSON value)
+const WORKING_DIR_PLACEHOLDER: &str = "\"@WORKING_DIR@\"";
+
+/// Writes the default configuration to the configuration file. The working
+/// directory is set to the platform-specific default. An existing
+/// configuration file is only overwritten if force is true
+pub fn write_defaults(force: bool) -> anyhow::Result<()> {
+    let path = cfg::file_path().ok_or_else(|| {
+        anyhow!("Could not determine path of configuration directory for this OS")
+    })?;
+    if path.exists() && !force {
+        return Err(anyhow!(
+            "Configuration file \"{}\" exists already. Use --force to overwrite it",
+            path.display()
+        ));
+    }
+
+    let working_dir = match video::default_working_dir() {
+        Some(dir) => serde_json::to_string(&dir).with_context(|| {
+            format!(
+                "Could not assemble default working directory \"{}\"",
+                dir.display()
+            )
+        })?,
+        None => {
+            warn!("Could not determine default working directory: Not set");
+            "null".to_string()
+        }
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Could not create directory \"{}\"", dir.display()))?;
+    }
+    fs::write(
+        &path,
+        DEFAULT_CFG.replace(WORKING_DIR_PLACEHOLDER, &working_dir),
+    )
+    .with_context(|| format!("Could not write configuration file \"{}\"", path.display()))?;
+
+    info!(
+        "Default configuration written to \"{}\". Add your OTR access data to decode videos",
+        path.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{
+    cleaning::{disk_usage, human_readable},
+    dirs::{self, DirKind},
+    journal::journal_path,
+    tagging::Tags,
+    Video,
+};
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Local};
+use log::*;
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// Number of bytes per GB (as used for --size-limit)
+const BYTES_PER_GB: f64 = 1_000_000_000.0;
+/// Number of seconds per day (as used for --older-than)
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Actions of sub command "archive"
+pub enum ArchiveAction {
+    /// List the archived videos
+    List,
+    /// Remove archived videos that are older than the given number of days
+    Prune(u64),
+    /// Remove the oldest archived videos until the archive does not require
+    /// more than the given number of GB
+    SizeLimit(f64),
+}
+
+/// Archived video incl. its journal
+struct Entry {
+    video: PathBuf,
+    key: String,
+    journal: Option<PathBuf>,
+    /// Point in time when the video was archived. That's the modification time
+    /// of the journal, or - if there is no journal - of the video
+    archived_at: SystemTime,
+    /// Size of video and journal in bytes
+    size: u64,
+}
+
+/// Executes an action on the archive directory (i.e., the directory where the
+/// decoded videos are moved to after they were cut). Videos are removed
+/// together with their journal files. Finally, the amount of reclaimed space is
+/// reported. If tag is given, only videos with that tag are considered
+pub fn archive(action: ArchiveAction, tag: Option<&str>) -> anyhow::Result<()> {
+    let mut entries = entries()?;
+    if let Some(_tag) = tag {
+        let tagged = Tags::load()?.keys_with(_tag);
+        entries.retain(|entry| tagged.contains(&entry.key));
+    }
+    // Oldest entries first
+    entries.sort_by_key(|entry| entry.archived_at);
+
+    let total: u64 = entries.iter().map(|entry| entry.size).sum();
+
+    let expired: Vec<&Entry> = match action {
+        ArchiveAction::List => {
+            for entry in &entries {
+                println!(
+                    "{}  {:>10}  {}",
+                    DateTime::<Local>::from(entry.archived_at).format("%Y-%m-%d %H:%M"),
+                    human_readable(entry.size),
+                    entry.video.file_name().unwrap().to_string_lossy()
+                );
+            }
+            println!(
+                "{} video(s), {} in total",
+                entries.len(),
+                human_readable(total)
+            );
+            return Ok(());
+        }
+        ArchiveAction::Prune(days) => {
+            let limit = SystemTime::now()
+                .checked_sub(Duration::from_secs(days * SECS_PER_DAY))
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            entries
+                .iter()
+                .filter(|entry| entry.archived_at < limit)
+                .collect()
+        }
+        ArchiveAction::SizeLimit(gb) => {
+            let limit = (gb * BYTES_PER_GB) as u64;
+            let mut size = total;
+            entries
+                .iter()
+                .take_while(|entry| {
+                    let exceeded = size > limit;
+                    size = size.saturating_sub(entry.size);
+                    exceeded
+                })
+                .collect()
+        }
+    };
+
+    let mut size: u64 = 0;
+    for entry in expired {
+        if let Err(err) = remove(entry) {
+            warn!("{:?}", err);
+            continue;
+        }
+        info!("Removed \"{}\"", entry.video.display());
+        size += entry.size;
+    }
+
+    info!("{} reclaimed", human_readable(size));
+
+    Ok(())
+}
+
+/// Archived videos. Files in the archive directory that are no videos (e.g.,
+/// journals or FFMS2 index files) are skipped
+fn entries() -> anyhow::Result<Vec<Entry>> {
+    let dir = dirs::working_sub_dir(&DirKind::Archive)?;
+    let mut entries: Vec<Entry> = vec![];
+
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("Could not read directory \"{}\"", dir.display()))?
+    {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let key = match Video::new(&path) {
+            Ok(_video) => _video.key().0.clone(),
+            Err(_) => continue,
+        };
+
+        let journal = Some(journal_path(&path)).filter(|journal| journal.is_file());
+        let archived_at = fs::metadata(journal.as_ref().unwrap_or(&path))
+            .and_then(|metadata| metadata.modified())
+            .with_context(|| {
+                format!(
+                    "Could not determine modification time of \"{}\"",
+                    path.display()
+                )
+            })?;
+        let size = disk_usage(&path) + journal.as_ref().map_or(0, |journal| disk_usage(journal));
+
+        entries.push(Entry {
+            video: path,
+            key,
+            journal,
+            archived_at,
+            size,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Removes an archived video and its journal
+fn remove(entry: &Entry) -> anyhow::Result<()> {
+    for path in [Some(&entry.video), entry.journal.as_ref()]
+        .into_iter()
+        .flatten()
+    {
+        fs::remove_file(path).map_err(|err| {
+            anyhow!(err).context(format!("Could not remove \"{}\"", path.display()))
+        })?;
+    }
+
+    Ok(())
+}
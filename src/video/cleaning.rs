@@ -0,0 +1,142 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::dirs::{
+    self, DirKind, CUTLIST_FILE_EXT, CUTTING_DIR_PREFIX, FFMS2_INDEX_EXTS, PARTIAL_CUT_FILE_PREFIX,
+    SYNCED_FILE_PREFIX,
+};
+
+use anyhow::{anyhow, Context};
+use log::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Removes temporary and stale artefacts: Cutting directories and generated
+/// cut list files in the temporary directory, FFMS2 index files in the working
+/// (sub) directories, partially written cut videos, and partially written
+/// decoded videos with corrected A/V offset. If dry_run is true,
+/// the artefacts are only reported, but not removed. Finally, the amount of
+/// (reclaimable) space is reported
+pub fn clean(dry_run: bool) -> anyhow::Result<()> {
+    let mut artefacts: Vec<PathBuf> = vec![];
+
+    // Artefacts in temporary directory
+    if let Some(tmp_dir) = dirs::tmp_dir() {
+        if tmp_dir.is_dir() {
+            for entry in fs::read_dir(&tmp_dir).with_context(|| {
+                format!(
+                    "Could not read temporary directory \"{}\"",
+                    tmp_dir.display()
+                )
+            })? {
+                let path = entry?.path();
+                let file_name = path.file_name().unwrap().to_string_lossy();
+                if (path.is_dir() && file_name.starts_with(&format!("{}-", CUTTING_DIR_PREFIX)))
+                    || (path.is_file()
+                        && path.extension().is_some_and(|ext| ext == CUTLIST_FILE_EXT))
+                {
+                    artefacts.push(path);
+                }
+            }
+        }
+    } else {
+        warn!("Could not determine temporary directory: Skipped");
+    }
+
+    // FFMS2 index files in working (sub) directories
+    for dir_kind in [
+        DirKind::Root,
+        DirKind::Decoded,
+        DirKind::Archive,
+        DirKind::Cut,
+    ] {
+        let dir = dirs::working_sub_dir(&dir_kind)?;
+        for entry in fs::read_dir(dir)
+            .with_context(|| format!("Could not read directory \"{}\"", dir.display()))?
+        {
+            let path = entry?.path();
+            let file_name = path.file_name().unwrap().to_string_lossy();
+            if path.is_file()
+                && (FFMS2_INDEX_EXTS
+                    .iter()
+                    .any(|ext| file_name.ends_with(&format!(".{}", ext)))
+                    || (dir_kind == DirKind::Cut && file_name.starts_with(PARTIAL_CUT_FILE_PREFIX))
+                    || (dir_kind == DirKind::Decoded && file_name.starts_with(SYNCED_FILE_PREFIX)))
+            {
+                artefacts.push(path);
+            }
+        }
+    }
+
+    let mut size: u64 = 0;
+    for artefact in artefacts {
+        let artefact_size = disk_usage(&artefact);
+
+        if dry_run {
+            info!("Would remove \"{}\"", artefact.display());
+            size += artefact_size;
+            continue;
+        }
+
+        let res = if artefact.is_dir() {
+            fs::remove_dir_all(&artefact)
+        } else {
+            fs::remove_file(&artefact)
+        };
+        if let Err(err) = res {
+            warn!(
+                "{:?}",
+                anyhow!(err).context(format!("Could not remove \"{}\"", artefact.display()))
+            );
+            continue;
+        }
+        info!("Removed \"{}\"", artefact.display());
+        size += artefact_size;
+    }
+
+    if dry_run {
+        info!("{} could be reclaimed", human_readable(size));
+    } else {
+        info!("{} reclaimed", human_readable(size));
+    }
+
+    Ok(())
+}
+
+/// Size of a file or a directory (incl. its content) in bytes. Entries that
+/// cannot be accessed are ignored
+pub(super) fn disk_usage(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| disk_usage(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Formats a number of bytes, e.g. as "1.5 GiB"
+pub(super) fn human_readable(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
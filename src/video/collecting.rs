@@ -4,20 +4,32 @@
 
 use super::{
     dirs::{self, DirKind},
-    Video,
+    Status, Video,
 };
+use crate::cfg;
 
 use anyhow::{anyhow, Context};
 use log::*;
-use std::{env, fs, path::Path};
+use std::{
+    collections::HashSet,
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
 
 /// Collects video files either from the submitted input paths, or (if no path
 /// was submitted) from the working (sub) directories. The corresponding Video
 /// instances are created and returned as vector, sorted by key (ascending) and
-/// status (descending).
-pub fn collect(in_videos: &[&Path]) -> anyhow::Result<Vec<Video>> {
+/// status (descending). Encoded videos whose file names are contained in
+/// changing (e.g., since they are still being downloaded) are skipped.
+pub fn collect(in_videos: &[&Path], changing: &HashSet<OsString>) -> anyhow::Result<Vec<Video>> {
     let mut videos: Vec<Video> = Vec::new();
 
+    // The files that are opened for writing are determined once for all videos,
+    // since that requires scanning all processes
+    let open_for_writing = files_open_for_writing();
+
     // Collect videos from input array
     for path in in_videos {
         // Turn path into an absolute path
@@ -36,6 +48,10 @@ pub fn collect(in_videos: &[&Path]) -> anyhow::Result<Vec<Video>> {
         // Create video from abs_path. Since the path is canonicalized during
         // activity, it is not necessary to canonicalize it here
         if let Ok(mut video) = Video::new(&abs_path) {
+            if video.is_being_written(&open_for_writing) {
+                warn!("\"{}\" is still being written: Ignored", path.display());
+                continue;
+            }
             video.move_to_working_dir()?;
             videos.push(video);
             continue;
@@ -52,10 +68,14 @@ pub fn collect(in_videos: &[&Path]) -> anyhow::Result<Vec<Video>> {
             DirKind::Decoded,
             DirKind::Cut,
         ] {
-            videos.append(&mut collect_videos_from_dir(&dir_kind).context(format!(
-                "Could not retrieve videos from \"{}\" sub directory",
-                &dir_kind
-            ))?);
+            videos.append(
+                &mut collect_videos_from_dir(&dir_kind, changing, &open_for_writing).context(
+                    format!(
+                        "Could not retrieve videos from \"{}\" sub directory",
+                        &dir_kind
+                    ),
+                )?,
+            );
         }
     }
 
@@ -68,8 +88,15 @@ pub fn collect(in_videos: &[&Path]) -> anyhow::Result<Vec<Video>> {
     Ok(videos)
 }
 
-/// Collect videos from the directory that is assigned to kind dir_kind
-fn collect_videos_from_dir(dir_kind: &DirKind) -> anyhow::Result<Vec<Video>> {
+/// Collect videos from the directory that is assigned to kind dir_kind. Encoded
+/// videos whose file names are contained in changing are skipped, as well as
+/// encoded videos that are being written (open_for_writing contains the files
+/// that are opened for writing)
+fn collect_videos_from_dir(
+    dir_kind: &DirKind,
+    changing: &HashSet<OsString>,
+    open_for_writing: &HashSet<PathBuf>,
+) -> anyhow::Result<Vec<Video>> {
     let mut videos: Vec<Video> = Vec::new();
     let dir = dirs::working_sub_dir(dir_kind)
         .context(format!("Could determine \"{}\" directory", &dir_kind))?;
@@ -83,12 +110,20 @@ fn collect_videos_from_dir(dir_kind: &DirKind) -> anyhow::Result<Vec<Video>> {
     {
         let file_ref = file.as_ref().unwrap();
 
-        if !file_ref.file_type()?.is_file() {
+        if !file_ref.file_type()?.is_file() || dirs::is_own_file(&file_ref.file_name()) {
             continue;
         }
 
         match Video::new(file_ref.path().as_path()) {
             Ok(mut video) => {
+                if video.status() == Status::Encoded && changing.contains(video.os_file_name()) {
+                    info!("\"{}\" is still changing: Skipped", video.file_name());
+                    continue;
+                }
+                if video.is_being_written(open_for_writing) {
+                    info!("\"{}\" is still being written: Skipped", video.file_name());
+                    continue;
+                }
                 video.move_to_working_dir()?;
                 videos.push(video);
             }
@@ -104,3 +139,70 @@ fn collect_videos_from_dir(dir_kind: &DirKind) -> anyhow::Result<Vec<Video>> {
 
     Ok(videos)
 }
+
+impl Video {
+    /// Returns true if the video is encoded and its file is still being written
+    /// (e.g., downloaded). That's assumed if the file was modified within the
+    /// configured stability window, or if it is contained in open_for_writing
+    /// (see files_open_for_writing)
+    pub(super) fn is_being_written(&self, open_for_writing: &HashSet<PathBuf>) -> bool {
+        if self.status() != Status::Encoded {
+            return false;
+        }
+
+        let is_recent = fs::metadata(&self.p)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age < cfg::stability_window());
+
+        is_recent || open_for_writing.contains(&self.p)
+    }
+}
+
+/// Returns the paths of the files that a process has opened for writing. They
+/// are determined from the file descriptors of all processes that are
+/// accessible (i.e., the processes of the user). If that is not possible, an
+/// empty set is returned
+#[cfg(target_os = "linux")]
+pub(super) fn files_open_for_writing() -> HashSet<PathBuf> {
+    let mut files: HashSet<PathBuf> = HashSet::new();
+
+    let procs = match fs::read_dir("/proc") {
+        Ok(_procs) => _procs,
+        Err(_) => return files,
+    };
+
+    for proc in procs.filter_map(|entry| entry.ok()) {
+        let fds = match fs::read_dir(proc.path().join("fd")) {
+            Ok(_fds) => _fds,
+            // Process has terminated or is not accessible
+            Err(_) => continue,
+        };
+        for fd in fds.filter_map(|entry| entry.ok()) {
+            if let Ok(target) = fs::read_link(fd.path()) {
+                // The flags of a file descriptor are given as octal number in
+                // the line "flags:" of the corresponding fdinfo file
+                let fd_info = proc.path().join("fdinfo").join(fd.file_name());
+                if let Some(flags) = fs::read_to_string(fd_info).ok().and_then(|info| {
+                    info.lines()
+                        .find_map(|line| line.strip_prefix("flags:"))
+                        .and_then(|flags| i32::from_str_radix(flags.trim(), 8).ok())
+                }) {
+                    if flags & libc::O_ACCMODE != libc::O_RDONLY {
+                        files.insert(target);
+                    }
+                }
+            }
+        }
+    }
+
+    files
+}
+
+/// Returns the paths of the files that a process has opened for writing. On
+/// this OS, that cannot be determined. Thus, an empty set is returned
+#[cfg(not(target_os = "linux"))]
+pub(super) fn files_open_for_writing() -> HashSet<PathBuf> {
+    HashSet::new()
+}
@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use super::cfg;
+use crate::cfg;
 
 use anyhow::{anyhow, Context};
 use const_format::formatcp;
@@ -11,12 +11,67 @@ use once_cell::sync::OnceCell;
 use std::{
     cmp::Eq,
     collections::HashMap,
-    fmt, fs,
-    path::{PathBuf, MAIN_SEPARATOR},
+    ffi::OsStr,
+    fmt,
+    fs::{self, DirBuilder},
+    os::unix::fs::{DirBuilderExt, MetadataExt},
+    path::{Path, PathBuf, MAIN_SEPARATOR},
 };
 
 pub const OTR_DEFAULT_DIR: &str = "OTR";
 
+/// Prefix of the directories otr-utils creates in its temporary directory to
+/// store intermediate results while cutting a video
+pub const CUTTING_DIR_PREFIX: &str = "cutting";
+/// Extension of the cut list files otr-utils generates in its temporary
+/// directory
+pub const CUTLIST_FILE_EXT: &str = "cutlist";
+/// Extensions of the FFMS2 index files that are created next to a video while
+/// cutting it
+pub const FFMS2_INDEX_EXTS: [&str; 3] = [
+    "ffindex",
+    "ffindex_track00.tc.txt",
+    "ffindex_track00.kf.txt",
+];
+
+/// Prefix of the (temporary) name under which a cut video is written. When it
+/// is complete, it is renamed to its final name
+pub const PARTIAL_CUT_FILE_PREFIX: &str = ".otr-partial-";
+
+/// Prefix of the (temporary) name under which a decoded video is written while
+/// its A/V offset is corrected. When it is complete, it replaces the decoded
+/// video
+pub const SYNCED_FILE_PREFIX: &str = ".otr-synced-";
+
+/// Name of the lock file in the working directory, which ensures that only one
+/// instance of otr processes the videos at a time
+pub const LOCK_FILE_NAME: &str = ".otr.lock";
+
+/// Name of the file in the working directory where the retry queue is stored
+pub const RETRY_FILE_NAME: &str = ".otr-retry.json";
+
+/// Name of the file in the working directory where the tags of the videos are
+/// stored
+pub const TAGS_FILE_NAME: &str = ".otr-tags.json";
+
+/// Name of the file in the working directory where the report about a run is
+/// written
+pub const REPORT_FILE_NAME: &str = "otr-report.html";
+
+/// Names of the files that otr itself keeps in the working directory. They are
+/// skipped when videos are collected from there
+const OWN_FILE_NAMES: [&str; 4] = [
+    LOCK_FILE_NAME,
+    RETRY_FILE_NAME,
+    TAGS_FILE_NAME,
+    REPORT_FILE_NAME,
+];
+
+/// Permissions of the working (sub) directories if otr creates them
+const WORKING_DIR_MODE: u32 = 0o755;
+/// Name of the file that is created to check whether a directory is writable
+const WRITE_CHECK_FILE_NAME: &str = ".otr-write-check";
+
 const SUB_PATH_ROOT: &str = "";
 const SUB_PATH_ENCODED: &str = "Encoded";
 const SUB_PATH_DECODED: &str = "Decoded";
@@ -74,9 +129,13 @@ pub fn working_sub_dir(kind: &DirKind) -> anyhow::Result<&'static PathBuf> {
                 DirKind::Archive,
             ] {
                 let sub_dir = working_dir.join(dir_kind.relative_path());
-                fs::create_dir_all(&sub_dir).with_context(|| {
-                    format!("Could not create sub directory \"{}\"", sub_dir.display())
-                })?;
+                DirBuilder::new()
+                    .recursive(true)
+                    .mode(WORKING_DIR_MODE)
+                    .create(&sub_dir)
+                    .with_context(|| {
+                        format!("Could not create sub directory \"{}\"", sub_dir.display())
+                    })?;
                 kind_to_path.insert(dir_kind, sub_dir);
             }
             Ok(kind_to_path)
@@ -95,6 +154,98 @@ pub fn working_sub_dir(kind: &DirKind) -> anyhow::Result<&'static PathBuf> {
     ))
 }
 
+/// Result of the check of a working (sub) directory
+pub enum DirCheck {
+    /// Directory is OK
+    Ok(String),
+    /// Problem that does not prevent otr from working
+    Warning(String),
+    /// Problem that prevents otr from working
+    Error(String),
+}
+
+/// Checks the working (sub) directories and returns the results. The
+/// directories are created if they do not exist. For each directory it is
+/// checked whether the user has write access, and whether it is on the same
+/// file system as the (root) working directory. The latter is required since
+/// videos are moved between these directories
+pub fn check_working_dirs() -> Vec<DirCheck> {
+    let mut checks: Vec<DirCheck> = vec![];
+
+    let root_dev = match working_dir().and_then(|dir| Ok(fs::metadata(dir)?.dev())) {
+        Ok(dev) => dev,
+        Err(err) => {
+            // The working directory might not exist yet. In this case, it is
+            // created together with the sub directories
+            trace!(
+                "Could not determine file system of working directory: {:?}",
+                err
+            );
+            0
+        }
+    };
+
+    for dir_kind in [
+        DirKind::Root,
+        DirKind::Encoded,
+        DirKind::Decoded,
+        DirKind::Cut,
+        DirKind::Archive,
+    ] {
+        let dir = match working_sub_dir(&dir_kind) {
+            Ok(_dir) => _dir,
+            Err(err) => {
+                checks.push(DirCheck::Error(format!("{:?}", err)));
+                return checks;
+            }
+        };
+
+        if let Err(err) = check_writable(dir) {
+            checks.push(DirCheck::Error(format!(
+                "No write access to \"{}\" directory \"{}\": {}",
+                dir_kind,
+                dir.display(),
+                err
+            )));
+            continue;
+        }
+
+        checks.push(match fs::metadata(dir) {
+            Ok(metadata) if root_dev != 0 && metadata.dev() != root_dev => {
+                DirCheck::Warning(format!(
+                    "\"{}\" directory \"{}\" is on a different file system than the working directory: Videos cannot be moved there",
+                    dir_kind,
+                    dir.display()
+                ))
+            }
+            Ok(_) => DirCheck::Ok(format!("\"{}\" directory \"{}\"", dir_kind, dir.display())),
+            Err(err) => DirCheck::Error(format!(
+                "Could not access \"{}\" directory \"{}\": {}",
+                dir_kind,
+                dir.display(),
+                err
+            )),
+        });
+    }
+
+    checks
+}
+
+/// Checks whether a directory is writable by creating and removing a file in
+/// it
+fn check_writable(dir: &Path) -> anyhow::Result<()> {
+    let path = dir.join(WRITE_CHECK_FILE_NAME);
+    fs::write(&path, [])?;
+    fs::remove_file(&path)?;
+    Ok(())
+}
+
+/// Returns true if file_name is the name of a file that otr itself keeps in the
+/// working directory (see OWN_FILE_NAMES)
+pub fn is_own_file(file_name: &OsStr) -> bool {
+    OWN_FILE_NAMES.iter().any(|name| file_name == *name)
+}
+
 /// (Root) working directory. It is set to the working dir path which was
 /// retrieved from the configuration. If there is no dir configured, the default
 /// working dir is used, which is <VIDEO_DIR_OF_YOUR_OS>/OTR. The determination
@@ -108,9 +259,9 @@ pub fn working_dir() -> anyhow::Result<&'static PathBuf> {
         } else {
             trace!("No working directory configured: Try default directory");
 
-            if let Some(video_dir) = dirs::video_dir() {
+            if let Some(_dir) = default_working_dir() {
                 trace!("Video directory determined: Assemble default working directory");
-                video_dir.join(OTR_DEFAULT_DIR)
+                _dir
             } else {
                 trace!("Video directory could not be determined");
                 return Err(anyhow!("Working directory could not be determined"));
@@ -122,3 +273,16 @@ pub fn working_dir() -> anyhow::Result<&'static PathBuf> {
         Ok(dir)
     })
 }
+
+/// Default working directory, which is <VIDEO_DIR_OF_YOUR_OS>/OTR. None is
+/// returned if the video directory of the OS cannot be determined
+pub fn default_working_dir() -> Option<PathBuf> {
+    dirs::video_dir().map(|dir| dir.join(OTR_DEFAULT_DIR))
+}
+
+/// Temporary directory of otr-utils, which is <CACHE_DIR_OF_YOUR_OS>/OTR. There,
+/// generated cut list files and intermediate results of cutting are stored. None
+/// is returned if the cache directory of the OS cannot be determined
+pub fn tmp_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(OTR_DEFAULT_DIR))
+}
@@ -0,0 +1,146 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{
+    dirs::{self, DirKind},
+    md5_checksum,
+    tagging::Tags,
+    Video,
+};
+
+use anyhow::{anyhow, Context};
+use chrono::NaiveDate;
+use log::*;
+use regex::Regex;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Copies the cut videos to the destination directory dest. If filter is
+/// given, only videos whose file name matches it are exported. If newer_than is
+/// given, only videos that were recorded on or after that date are exported. If
+/// tag is given, only videos with that tag are exported. Each copy is
+/// verified by comparing the MD5 checksums of source and target. Incomplete
+/// copies of a previous export are resumed, and videos that were exported
+/// completely already are skipped. An error while exporting a video does not
+/// stop the export of the others
+pub fn export(
+    dest: &Path,
+    filter: Option<&Regex>,
+    newer_than: Option<NaiveDate>,
+    tag: Option<&str>,
+) -> anyhow::Result<()> {
+    if !dest.is_dir() {
+        return Err(anyhow!(
+            "Destination \"{}\" is not a directory",
+            dest.display()
+        ));
+    }
+
+    let dir = dirs::working_sub_dir(&DirKind::Cut)?;
+    let tagged = tag
+        .map(|_tag| Tags::load().map(|tags| tags.keys_with(_tag)))
+        .transpose()?;
+    let mut n_failed = 0;
+
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("Could not read directory \"{}\"", dir.display()))?
+    {
+        let video = match Video::new(&entry?.path()) {
+            Ok(_video) => _video,
+            Err(_) => continue,
+        };
+        if filter.is_some_and(|re| !re.is_match(&video.file_name())) {
+            continue;
+        }
+        if tagged
+            .as_ref()
+            .is_some_and(|keys| !keys.contains(&video.key().0))
+        {
+            continue;
+        }
+        if let Some(date) = newer_than {
+            match video.recorded_at() {
+                Some(recorded_at) if recorded_at.date() >= date => (),
+                Some(_) => continue,
+                None => {
+                    warn!(
+                        "Could not determine recording date of \"{}\": Skipped",
+                        video.file_name()
+                    );
+                    continue;
+                }
+            }
+        }
+
+        if let Err(err) = export_video(&video, dest) {
+            error!("\"{}\":\n{:?}\n", video.file_name(), err);
+            n_failed += 1;
+        }
+    }
+
+    if n_failed > 0 {
+        return Err(anyhow!("{} video(s) could not be exported", n_failed));
+    }
+
+    Ok(())
+}
+
+/// Copies a video to the destination directory and verifies the copy
+fn export_video(video: &Video, dest: &Path) -> anyhow::Result<()> {
+    let source = video.p.as_path();
+    let target = dest.join(video.os_file_name());
+    let source_len = fs::metadata(source)?.len();
+    let checksum = md5_checksum(source)?;
+
+    // Size of an already existing (potentially incomplete) copy
+    let mut target_len = fs::metadata(&target)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+
+    if target_len == source_len {
+        if md5_checksum(&target)? == checksum {
+            info!("Exported already: \"{}\"", video.file_name());
+            return Ok(());
+        }
+        // Copy is corrupt: Copy video again
+        target_len = 0;
+    }
+    if target_len > source_len {
+        target_len = 0;
+    }
+
+    if target_len > 0 {
+        info!("Resuming export of \"{}\" ...", video.file_name());
+    } else {
+        info!("Exporting \"{}\" ...", video.file_name());
+    }
+
+    // Copy (remaining part of the) video
+    let mut source_file = File::open(source)?;
+    source_file.seek(SeekFrom::Start(target_len))?;
+    let mut target_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(target_len == 0)
+        .append(target_len > 0)
+        .open(&target)
+        .with_context(|| format!("Could not open \"{}\"", target.display()))?;
+    io::copy(&mut source_file, &mut target_file)
+        .with_context(|| format!("Could not copy video to \"{}\"", target.display()))?;
+
+    // Verify copy
+    if md5_checksum(&target)? != checksum {
+        fs::remove_file(&target)?;
+        return Err(anyhow!(
+            "Checksum of exported video does not match checksum of original: Export removed"
+        ));
+    }
+
+    info!("Exported \"{}\"", video.file_name());
+
+    Ok(())
+}
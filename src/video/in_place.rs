@@ -0,0 +1,60 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{cut_error, cut_via_partial};
+
+use anyhow::anyhow;
+use log::*;
+use otr_utils::cutting::{CutError, CutlistCtrl};
+use std::path::Path;
+
+/// Cuts a decoded video that is stored outside of the working directories and
+/// writes the cut video to out. Neither video is moved, and the working
+/// directories are not used at all. Cut lists are retrieved from the cut list
+/// provider based on the file name of the video, as for videos in the working
+/// directories. As there, the cut video is written under a temporary name and
+/// renamed when it is complete (and - if verify is true - verified)
+pub fn cut_in_place(
+    video: &Path,
+    out: &Path,
+    cutlist_ctrl: &CutlistCtrl,
+    verify: bool,
+) -> anyhow::Result<()> {
+    if !video.is_file() {
+        return Err(anyhow!("\"{}\" is no file", video.display()));
+    }
+    // otr-utils requires file names that are valid UTF-8 for cutting
+    if video
+        .file_name()
+        .and_then(|file_name| file_name.to_str())
+        .is_none()
+    {
+        return Err(anyhow!(
+            "Could not cut \"{}\" since its file name is not valid UTF-8",
+            video.display()
+        ));
+    }
+    if out.exists() {
+        return Err(anyhow!(
+            "Could not cut \"{}\" since \"{}\" exists already",
+            video.display(),
+            out.display()
+        ));
+    }
+    if out.file_name().is_none() {
+        return Err(anyhow!(
+            "\"{}\" is no valid path for the cut video",
+            out.display()
+        ));
+    }
+
+    info!("Cutting \"{}\" ...", video.display());
+
+    let res = cut_via_partial(video, out, cutlist_ctrl, verify);
+    if let Ok(()) | Err(CutError::CutlistSubmissionFailed(_)) = res {
+        info!("Cut \"{}\" to \"{}\"", video.display(), out.display());
+    }
+
+    res.map_err(cut_error)
+}
@@ -0,0 +1,106 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{md5_checksum, Video};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, SecondsFormat};
+use otr_utils::cutting::{CutlistAccessType, CutlistCtrl};
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+/// Extension of journal files
+const JOURNAL_EXT: &str = "json";
+
+/// Journal of the processing of a video. It is stored next to the archived
+/// decoded video, so that the archive remains self-describing
+#[derive(serde::Serialize)]
+struct Journal<'a> {
+    video: String,
+    key: String,
+    /// Point in time when the video was decoded (modification time of the
+    /// decoded file)
+    decoded_at: Option<String>,
+    cut_at: String,
+    cut_video: String,
+    cutlist: Cutlist<'a>,
+    checksums: Checksums,
+}
+
+/// Cut list that was applied to cut the video
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Cutlist<'a> {
+    /// Cut list was selected automatically from cutlist.at
+    Auto,
+    Intervals(&'a str),
+    File(&'a Path),
+    Id(u64),
+}
+/// Support conversion of CutlistAccessType into Cutlist
+impl<'a> From<&CutlistAccessType<'a>> for Cutlist<'a> {
+    fn from(access_type: &CutlistAccessType<'a>) -> Self {
+        match access_type {
+            CutlistAccessType::Auto => Cutlist::Auto,
+            CutlistAccessType::Direct(intervals) => Cutlist::Intervals(intervals),
+            CutlistAccessType::File(file) => Cutlist::File(file),
+            CutlistAccessType::ID(id) => Cutlist::Id(*id),
+        }
+    }
+}
+
+/// MD5 checksums of the decoded and the cut video
+#[derive(serde::Serialize)]
+struct Checksums {
+    decoded: String,
+    cut: String,
+}
+
+impl Video {
+    /// Writes the journal for a video that was cut. archived_path is the path
+    /// of the decoded video in the archive directory, cut_path the path of the
+    /// cut video. The journal is stored next to the archived video
+    pub(super) fn write_journal(
+        &self,
+        archived_path: &Path,
+        cut_path: &Path,
+        cutlist_ctrl: &CutlistCtrl,
+    ) -> anyhow::Result<()> {
+        let journal = Journal {
+            video: self.file_name().into_owned(),
+            key: self.key().to_string(),
+            decoded_at: fs::metadata(archived_path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .map(|modified| {
+                    DateTime::<Local>::from(modified).to_rfc3339_opts(SecondsFormat::Secs, false)
+                }),
+            cut_at: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            cut_video: cut_path.file_name().unwrap().to_string_lossy().into_owned(),
+            cutlist: Cutlist::from(&cutlist_ctrl.access_type),
+            checksums: Checksums {
+                decoded: md5_checksum(archived_path)?,
+                cut: md5_checksum(cut_path)?,
+            },
+        };
+
+        let path = journal_path(archived_path);
+        let file = File::create(&path)
+            .with_context(|| format!("Could not create journal \"{}\"", path.display()))?;
+        serde_json::to_writer_pretty(file, &journal)
+            .with_context(|| format!("Could not write journal \"{}\"", path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// Path of the journal of an archived video: The path of the video with the
+/// additional extension "json"
+pub(super) fn journal_path(archived_path: &Path) -> PathBuf {
+    let mut path = archived_path.as_os_str().to_os_string();
+    path.push(format!(".{}", JOURNAL_EXT));
+    PathBuf::from(path)
+}
@@ -0,0 +1,139 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::dirs::{self, LOCK_FILE_NAME};
+
+use anyhow::{anyhow, Context};
+use log::*;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+
+/// Interval in which a waiting instance of otr checks whether the lock was
+/// released
+const WAIT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Lock that ensures that only one instance of otr processes the videos in the
+/// working directory at a time. It is represented by a lock file in the working
+/// directory that contains the process ID of the instance that holds the lock.
+/// The lock file is removed when the lock is dropped
+pub struct Lock {
+    path: PathBuf,
+    /// Whether the lock file was created by this instance. That's not the case
+    /// for child processes (see option --per-video-timeout), which run while
+    /// their parent holds the lock
+    is_owner: bool,
+}
+
+impl Lock {
+    /// Acquires the lock. If another instance of otr holds it, an error is
+    /// returned or - if wait is true - the function blocks until the other
+    /// instance released it. Lock files of instances that are no longer running
+    /// (e.g., since they were killed) are removed
+    pub fn acquire(wait: bool) -> anyhow::Result<Self> {
+        let path = dirs::working_dir()?.join(LOCK_FILE_NAME);
+        let mut is_waiting = false;
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id()).with_context(|| {
+                        format!("Could not write lock file \"{}\"", path.display())
+                    })?;
+                    return Ok(Lock {
+                        path,
+                        is_owner: true,
+                    });
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => (),
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("Could not create lock file \"{}\"", path.display())
+                    })
+                }
+            }
+
+            // The lock file might have been removed in the meantime
+            let pid = match fs::read_to_string(&path) {
+                Ok(content) => content.trim().parse::<u32>().ok(),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("Could not read lock file \"{}\"", path.display())
+                    })
+                }
+            };
+
+            match pid {
+                Some(_pid) if _pid == parent_id() => {
+                    return Ok(Lock {
+                        path,
+                        is_owner: false,
+                    })
+                }
+                Some(_pid) if is_running(_pid) => {
+                    if !wait {
+                        return Err(anyhow!(
+                            "Another instance of otr (PID {}) is processing videos. Use --wait to wait until it is finished",
+                            _pid
+                        ));
+                    }
+                    if !is_waiting {
+                        info!(
+                            "Waiting for another instance of otr (PID {}) to finish ...",
+                            _pid
+                        );
+                        is_waiting = true;
+                    }
+                    thread::sleep(WAIT_INTERVAL);
+                }
+                _ => {
+                    warn!("Removing stale lock file \"{}\"", path.display());
+                    match fs::remove_file(&path) {
+                        Ok(()) => (),
+                        Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                        Err(err) => {
+                            return Err(err).with_context(|| {
+                                format!("Could not remove stale lock file \"{}\"", path.display())
+                            })
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if !self.is_owner {
+            return;
+        }
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!(
+                "{:?}",
+                anyhow!(err).context(format!(
+                    "Could not remove lock file \"{}\"",
+                    self.path.display()
+                ))
+            );
+        }
+    }
+}
+
+/// Process ID of the parent process of otr
+fn parent_id() -> u32 {
+    unsafe { libc::getppid() as u32 }
+}
+
+/// Checks whether a process with the given ID is running. If it exists, but
+/// belongs to another user, it is regarded as running as well
+fn is_running(pid: u32) -> bool {
+    let res = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    res == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
@@ -2,25 +2,58 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod archiving;
+mod cleaning;
 mod collecting;
 mod dirs;
+mod exporting;
+mod in_place;
+mod journal;
+mod locking;
+mod purging;
+mod reporting;
+mod retrying;
+mod syncing;
+mod tagging;
+mod timeout;
+mod uploading;
+mod watching;
 
+pub use archiving::{archive, ArchiveAction};
+pub use cleaning::clean;
 pub use collecting::collect;
+pub use dirs::{check_working_dirs, default_working_dir, tmp_dir, DirCheck};
+pub use exporting::export;
+pub use in_place::cut_in_place;
+pub use locking::Lock;
+pub use purging::purge;
+pub use reporting::Report;
+pub use retrying::RetryQueue;
+pub use tagging::tag;
+pub use uploading::UPLOAD_TOOL;
+pub use watching::Watcher;
+
+use crate::{cutlist, probe};
 
-use crate::cfg;
 use anyhow::{anyhow, Context};
+use chrono::{NaiveDate, NaiveDateTime};
 use dirs::DirKind;
 use lazy_static::lazy_static;
 use log::*;
-use otr_utils::{
-    cutting::{self, CutError, CutlistAccessType, CutlistCtrl, CutlistRating},
-    decoding,
-};
+use md5::{Digest, Md5};
+use otr_utils::cutting::{self, CutError, CutlistCtrl};
+use otr_utils::decoding;
 use regex::Regex;
 use std::{
-    cmp, fmt, fs,
+    borrow::Cow,
+    cmp,
+    error::Error,
+    ffi::{OsStr, OsString},
+    fmt, fs, io,
     marker::Copy,
     path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
 };
 
 /// Key of an OTR video. That's the left part of the file name ending with
@@ -67,19 +100,27 @@ impl PartialOrd for Status {
         Some(cmp::Ordering::Less)
     }
 }
-/// Support iteration over status value: Encoded -> Decoded -> Cut -> None
-impl Iterator for Status {
-    type Item = Status;
-
-    fn next(&mut self) -> Option<Self::Item> {
+/// Support the use of "{}" format specifier
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Encoded => write!(f, "encoded"),
+            Status::Decoded => write!(f, "decoded"),
+            Status::Cut => write!(f, "cut"),
+        }
+    }
+}
+impl Status {
+    /// Status a video gets if the next processing stage is executed for it:
+    /// Encoded -> Decoded -> Cut. None is returned for cut videos
+    fn next(self) -> Option<Status> {
         match self {
             Status::Encoded => Some(Status::Decoded),
             Status::Decoded => Some(Status::Cut),
             Status::Cut => None,
         }
     }
-}
-impl Status {
+
     /// Map a video status to the corresponding directory kind
     fn as_dir_kind(self) -> DirKind {
         match self {
@@ -90,6 +131,80 @@ impl Status {
     }
 }
 
+/// Processing stages of a video
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stage {
+    Decode,
+    Cut,
+}
+/// Support the use of "{}" format specifier
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stage::Decode => write!(f, "decode"),
+            Stage::Cut => write!(f, "cut"),
+        }
+    }
+}
+/// Support conversion of strings such as "cut" into Stage
+impl FromStr for Stage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "decode" => Ok(Stage::Decode),
+            "cut" => Ok(Stage::Cut),
+            _ => Err(format!(
+                "\"{}\" is not a valid stage (valid stages are \"decode\" and \"cut\")",
+                s
+            )),
+        }
+    }
+}
+impl Stage {
+    /// Status a video must have to be processed by the stage
+    fn source_status(self) -> Status {
+        match self {
+            Stage::Decode => Status::Encoded,
+            Stage::Cut => Status::Decoded,
+        }
+    }
+}
+
+/// Special error type for processing videos to be able to handle specific
+/// situations - e.g., if the processing of a video took too long
+#[derive(Debug)]
+pub enum ProcessingError {
+    /// Failure that was injected deliberately to test error handling
+    Injected(Stage),
+    /// There is no cut list for the video (yet)
+    NoCutlist,
+    /// OTR access data (user, password) is required to decode the video
+    NoAccessData,
+    Timeout(Duration),
+}
+/// Support the use of "{}" format specifier
+impl fmt::Display for ProcessingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProcessingError::Injected(stage) => {
+                write!(f, "Injected failure at stage \"{}\"", stage)
+            }
+            ProcessingError::NoCutlist => write!(f, "No cut list exists for video"),
+            ProcessingError::NoAccessData => {
+                write!(f, "OTR user and password required to decode video")
+            }
+            ProcessingError::Timeout(timeout) => write!(
+                f,
+                "Processing was aborted since it took longer than {:?}",
+                timeout
+            ),
+        }
+    }
+}
+/// Support conversion of ProcessingError into anyhow::Error
+impl Error for ProcessingError {}
+
 /// Video file downloaded from OTR, incl. its path, key and status
 pub struct Video {
     p: PathBuf,
@@ -104,9 +219,18 @@ lazy_static! {
     // i.e., either encoded or decoded.
     static ref RE_UNCUT_VIDEO: Regex =
         Regex::new(r"^([^\.]+_\d{2}.\d{2}.\d{2}_\d{2}-\d{2}_[^_]+_\d+_TVOON_DE)\.[^\.]+(?P<fmt>\.(HQ|HD))?(?P<ext>\.[^\.]+)(?P<encext>\.otrkey)?$").unwrap();
+    // Analyze the name of a (potential) audio recording that is not cut. In
+    // contrast to videos, the extension directly follows the key (e.g.,
+    // "..._TVOON_DE.mp3.otrkey")
+    static ref RE_UNCUT_AUDIO: Regex =
+        Regex::new(r"^([^\.]+_\d{2}.\d{2}.\d{2}_\d{2}-\d{2}_[^_]+_\d+_TVOON_DE)(?P<ext>\.(mp3|ac3|aac|m4a))(?P<encext>\.otrkey)?$").unwrap();
     // Analyze the name of a (potential) video file that is cut
     static ref RE_CUT_VIDEO: Regex =
         Regex::new(r"^([^\.]+_\d{2}.\d{2}.\d{2}_\d{2}-\d{2}_[^_]+_\d+_TVOON_DE)\.(.*cut\..+)$").unwrap();
+    // Extract the point in time of the recording (YY.MM.DD_hh-mm) from the key
+    // of a video
+    static ref RE_RECORDING_TIME: Regex =
+        Regex::new(r"_(\d{2}).(\d{2}).(\d{2})_(\d{2})-(\d{2})_[^_]+_\d+_TVOON_DE").unwrap();
 }
 
 /// Support ordering of videos: By key (ascending), status (descending)
@@ -162,9 +286,38 @@ impl Video {
         &self.e
     }
 
-    // File name of a Video (i.e., the last part of its path)
-    pub fn file_name(&self) -> &str {
-        self.p.file_name().unwrap().to_str().unwrap()
+    /// Returns the error of the video if it is a ProcessingError - i.e., if
+    /// the processing failed for a reason that can be handled specifically
+    pub fn processing_error(&self) -> Option<&ProcessingError> {
+        self.error().as_ref()?.downcast_ref::<ProcessingError>()
+    }
+
+    // File name of a Video (i.e., the last part of its path) for display and
+    // matching. Invalid UTF-8 sequences are replaced
+    pub fn file_name(&self) -> Cow<'_, str> {
+        self.os_file_name().to_string_lossy()
+    }
+
+    // File name of a Video as it is stored in the file system
+    fn os_file_name(&self) -> &OsStr {
+        // Since the video path was already checked for compliance before, it is
+        // OK to simply unwrap the result
+        self.p.file_name().unwrap()
+    }
+
+    /// Point in time when the video was recorded (as local time of the TV
+    /// station), taken from its key. OTR only exists since 2005, thus the year
+    /// YY is interpreted as 20YY. None is returned if the key does not contain
+    /// a valid date and time
+    pub fn recorded_at(&self) -> Option<NaiveDateTime> {
+        let captures = RE_RECORDING_TIME.captures(&self.key().0)?;
+        let number = |i: usize| captures.get(i).unwrap().as_str().parse::<u32>().unwrap();
+
+        NaiveDate::from_ymd_opt(2000 + number(1) as i32, number(2), number(3))?.and_hms_opt(
+            number(4),
+            number(5),
+            0,
+        )
     }
 
     // True if video is already cut, false otherwise.
@@ -175,16 +328,19 @@ impl Video {
     /// Cut a decoded Video. The video status and path is updated accordingly.
     /// The video file is moved accordingly. The real thing is done by _cut, the
     /// private counterpart function.
-    /// cutlist_access specified how to (try to) get an appropriate cut list,
-    /// min_cutlist_rating specifies the minimum rating a cut list must have when
-    /// automatically selected from the cut list provider
+    /// cutlist_ctrl specifies how to (try to) get an appropriate cut list, which
+    /// minimum rating a cut list must have when automatically selected from the
+    /// cut list provider, and whether it shall be submitted to the provider.
+    /// If verify is true, the cut video is verified before it is accepted. If
+    /// av_sync_threshold is set, a constant A/V offset above that threshold is
+    /// corrected before cutting
     pub fn cut(
         &mut self,
-        cutlist_access: CutlistAccessType,
-        cutlist_rating: Option<CutlistRating>,
-        min_cutlist_rating: Option<CutlistRating>,
+        cutlist_ctrl: &CutlistCtrl,
+        verify: bool,
+        av_sync_threshold: Option<Duration>,
     ) {
-        if let Err(err) = self._cut(cutlist_access, cutlist_rating, min_cutlist_rating) {
+        if let Err(err) = self._cut(cutlist_ctrl, verify, av_sync_threshold) {
             self.e = Some(err)
         }
     }
@@ -198,67 +354,91 @@ impl Video {
         }
     }
 
+    /// Let the given processing stage fail for the video without executing it.
+    /// This is only done if the video has the status that is required for the
+    /// stage. It allows testing the error handling of setups around otr without
+    /// touching the video file
+    pub fn fail(&mut self, stage: Stage) {
+        if self.status() != stage.source_status() {
+            return;
+        }
+
+        info!(
+            "Failing \"{}\" deliberately at stage {}",
+            self.file_name(),
+            stage
+        );
+
+        self.e = Some(anyhow!(ProcessingError::Injected(stage)))
+    }
+
     /// Create a new video from a video file path
     fn new<P>(path: P) -> anyhow::Result<Self>
     where
         P: Into<PathBuf> + Copy,
     {
         if let Some(file_name) = path.into().file_name() {
-            if let Some(file_name_str) = file_name.to_str() {
-                // Check if path represents a cut video file (the check for cut
-                // video files must be done before the check for uncut video
-                // file since cut video files in some cases also match the
-                // regex for uncut files)
-                if RE_CUT_VIDEO.is_match(file_name_str) {
-                    // Assemble Video instance
-                    let captures = RE_CUT_VIDEO.captures(file_name_str).unwrap();
-                    let appendix = captures
-                        .get(2)
-                        .unwrap()
-                        .as_str()
-                        .replace("cut.", "")
-                        .replace(".mpg", "");
-                    // Assemble Video instance
-                    return Ok(Video {
-                        p: fs::canonicalize(path.into()).context(format!(
-                            "Could not create video from path {}",
-                            path.into().display()
-                        ))?,
-                        k: Key::from(
-                            captures.get(1).unwrap().as_str().to_string()
-                                + if appendix.starts_with('.') { "" } else { "." }
-                                + &appendix,
-                        ),
-                        s: Status::Cut,
-                        e: None,
-                    });
-                }
-                // Check if path represents an encoded or decoded video file
-                if RE_UNCUT_VIDEO.is_match(file_name_str) {
-                    // Assemble Video instance
-                    let captures = RE_UNCUT_VIDEO.captures(file_name_str).unwrap();
-                    return Ok(Video {
-                        p: fs::canonicalize(path.into()).context(format!(
-                            "Could not create video from path {}",
-                            path.into().display()
-                        ))?,
-                        k: Key::from(
-                            captures.get(1).unwrap().as_str().to_string()
-                                + if let Some(fmt) = captures.name("fmt") {
-                                    fmt.as_str()
-                                } else {
-                                    ""
-                                }
-                                + captures.name("ext").unwrap().as_str(),
-                        ),
-                        s: if captures.name("encext").is_some() {
-                            Status::Encoded
-                        } else {
-                            Status::Decoded
-                        },
-                        e: None,
-                    });
-                }
+            // File names that are not valid UTF-8 are analyzed in an escaped
+            // form, so that the key is unique for each file name. The path of
+            // the video keeps the original file name
+            let file_name_str: &str = &escaped_file_name(file_name);
+            // Check if path represents a cut video file (the check for cut
+            // video files must be done before the check for uncut video
+            // file since cut video files in some cases also match the
+            // regex for uncut files)
+            if RE_CUT_VIDEO.is_match(file_name_str) {
+                // Assemble Video instance
+                let captures = RE_CUT_VIDEO.captures(file_name_str).unwrap();
+                // The appendix of cut videos starts with "mpg." (e.g.,
+                // "mpg.HQ.cut.avi"). Like for uncut videos, this part is not
+                // relevant for the key
+                let appendix = captures.get(2).unwrap().as_str().replace("cut.", "");
+                let appendix = appendix.strip_prefix("mpg.").unwrap_or(&appendix);
+                // Assemble Video instance
+                return Ok(Video {
+                    p: fs::canonicalize(path.into()).context(format!(
+                        "Could not create video from path {}",
+                        path.into().display()
+                    ))?,
+                    k: Key::from(
+                        captures.get(1).unwrap().as_str().to_string()
+                            + if appendix.starts_with('.') { "" } else { "." }
+                            + appendix,
+                    ),
+                    s: Status::Cut,
+                    e: None,
+                });
+            }
+            // Check if path represents an encoded or decoded audio recording
+            // or video file (the check for audio recordings must be done
+            // before the check for video files since encoded audio recordings
+            // also match the regex for video files)
+            if let Some(captures) = RE_UNCUT_AUDIO
+                .captures(file_name_str)
+                .or_else(|| RE_UNCUT_VIDEO.captures(file_name_str))
+            {
+                // Assemble Video instance
+                return Ok(Video {
+                    p: fs::canonicalize(path.into()).context(format!(
+                        "Could not create video from path {}",
+                        path.into().display()
+                    ))?,
+                    k: Key::from(
+                        captures.get(1).unwrap().as_str().to_string()
+                            + if let Some(fmt) = captures.name("fmt") {
+                                fmt.as_str()
+                            } else {
+                                ""
+                            }
+                            + captures.name("ext").unwrap().as_str(),
+                    ),
+                    s: if captures.name("encext").is_some() {
+                        Status::Encoded
+                    } else {
+                        Status::Decoded
+                    },
+                    e: None,
+                });
             }
         }
         Err(anyhow!(
@@ -267,15 +447,31 @@ impl Video {
         ))
     }
 
+    /// Changes the video to status to (stages can be skipped, e.g. an encoded
+    /// video becomes cut if it was decoded and cut before). The path of the
+    /// video is changed accordingly. The video file is not moved: That must
+    /// have been done before (e.g., by decoding or cutting)
+    fn change_status(&mut self, to: Status) -> anyhow::Result<()> {
+        // NOTE: The new status must not be set before path() is executed
+        //       since path() uses the current status !!!
+        self.p = self.path(to)?;
+        trace!(
+            "\"{}\" changed from status {} to {}",
+            self.file_name(),
+            self.s,
+            to
+        );
+        self.s = to;
+
+        Ok(())
+    }
+
     // Changes the videos to the next status (i.e., if its in status encoded,
     // it is set to decoded, and if it is in status decoded it will be set to
     // cut). The video path is changed accordingly.
     fn change_to_next_status(&mut self) -> anyhow::Result<()> {
         if let Some(next_status) = self.s.next() {
-            // NOTE: The new status must not we set before next_path() is
-            //       executed first since next_path() uses the status !!!
-            self.p = self.next_path()?;
-            self.s = next_status;
+            self.change_status(next_status)?;
         }
         Ok(())
     }
@@ -283,64 +479,49 @@ impl Video {
     /// Cut a decoded Video (private cut function which is wrapped by its public
     /// counterpart). The video status and path, and the video file is moved
     /// accordingly.
-    /// cutlist_access specifies how to (try to) get an appropriate cut list,
-    /// min_cutlist_rating specifies the minimum rating a cut list must have when
-    /// automatically selected from the cut list provider
+    /// cutlist_ctrl specifies how to (try to) get an appropriate cut list, which
+    /// minimum rating a cut list must have when automatically selected from the
+    /// cut list provider, and whether it shall be submitted to the provider
     fn _cut(
         &mut self,
-        cutlist_access: CutlistAccessType,
-        cutlist_rating: Option<CutlistRating>,
-        min_cutlist_rating: Option<CutlistRating>,
+        cutlist_ctrl: &CutlistCtrl,
+        verify: bool,
+        av_sync_threshold: Option<Duration>,
     ) -> anyhow::Result<()> {
         // Nothing to do if video is not in status "decoded"
         if self.status() != Status::Decoded {
             return Ok(());
         }
 
-        info!("Cutting \"{}\" ...", self.file_name());
+        // otr-utils requires file names that are valid UTF-8 for cutting
+        if self.os_file_name().to_str().is_none() {
+            return Err(anyhow!(
+                "Could not cut video since its file name is not valid UTF-8"
+            ));
+        }
 
-        // Cut video and move cut video to corresponding directory
-        match cutting::cut(
-            &self,
-            self.next_path()?,
-            &CutlistCtrl {
-                access_type: cutlist_access,
-                min_rating: min_cutlist_rating.or_else(cfg::min_cutlist_rating),
-                rating: cutlist_rating.unwrap_or(cfg::cutlist_rating()),
-                submit: cfg::submit_cutlists(),
-                access_token: cfg::cutlist_at_access_token(),
-            },
-        ) {
-            Ok(()) => {
-                // In case the video was cut suceesfully and a (potential)
-                // submission of the cut list was done successfully, move decoded
-                // video to archive directory and return with Ok
-                self.move_to_archive_dir()?;
-
-                // Update video (status, path)
-                self.change_to_next_status()?;
-
-                info!("Cut \"{}\"", self.file_name());
-
-                Ok(())
-            }
-            Err(CutError::CutlistSubmissionFailed(err)) => {
-                // In case the video was cut successfully, but submission of cut
-                // list failed, move decoded video to archive directory and
-                // return with Error
-                self.move_to_archive_dir()?;
+        // A constant offset between audio and video is corrected before cutting
+        // if that is configured
+        if let Some(threshold) = av_sync_threshold {
+            self.correct_av_offset(threshold)
+                .context("Could not correct A/V offset")?;
+        }
 
-                // Update video (status, path)
-                self.change_to_next_status()?;
+        info!("Cutting \"{}\" ...", self.file_name());
 
-                info!("Cut \"{}\"", self.file_name());
+        let res = cut_via_partial(&self.p, &self.next_path()?, cutlist_ctrl, verify);
 
-                Err(err.context("Video was cut, but cut list could not be submitted to cutlist.at"))
-            }
-            Err(CutError::Any(err)) => Err(err.context("Could not cut video")),
-            Err(CutError::Default) => Err(anyhow!("Could not cut video for an unknown reason")),
-            Err(CutError::NoCutlist) => Err(anyhow!("No cut list exists for video")),
+        // In case the video was cut successfully (even if the submission of the
+        // cut list failed), move decoded video to archive directory and update
+        // the video (status, path)
+        if let Ok(()) | Err(CutError::CutlistSubmissionFailed(_)) = res {
+            self.move_to_archive_dir(cutlist_ctrl)?;
+            self.change_to_next_status()?;
+
+            info!("Cut \"{}\"", self.file_name());
         }
+
+        res.map_err(cut_error)
     }
 
     /// Decode an encoded video (private decode function which is wrapped by its
@@ -352,12 +533,16 @@ impl Video {
             return Ok(());
         }
 
-        let (user, password) =
-            if let Some((_user, _password)) = access_data.or_else(cfg::otr_access_data) {
-                (_user, _password)
-            } else {
-                return Err(anyhow!("OTR user and password required to decode video"));
-            };
+        // Skip decoding if the video was decoded already
+        if self.reuse_decoded()? {
+            return Ok(());
+        }
+
+        let (user, password) = if let Some((_user, _password)) = access_data {
+            (_user, _password)
+        } else {
+            return Err(anyhow!(ProcessingError::NoAccessData));
+        };
 
         info!("Decoding {} ...", self.file_name());
 
@@ -372,24 +557,73 @@ impl Video {
         Ok(())
     }
 
-    // Move decoded video to archive directory
-    fn move_to_archive_dir(&self) -> anyhow::Result<()> {
+    // Checks if a decoded counterpart of an encoded video exists already -
+    // either in the directory for decoded videos or in the archive directory. In
+    // this case, the video is changed to that counterpart (a counterpart from
+    // the archive is moved back to the directory for decoded videos, unless the
+    // video was cut already) and true is returned. Otherwise, false is returned
+    fn reuse_decoded(&mut self) -> anyhow::Result<bool> {
+        let decoded_path = self.next_path()?;
+
+        if !decoded_path.exists() {
+            let archived_path =
+                dirs::working_sub_dir(&DirKind::Archive)?.join(decoded_path.file_name().unwrap());
+            if !archived_path.exists() {
+                return Ok(false);
+            }
+
+            // If the video was cut already, there is nothing left to do
+            if self.path(Status::Cut)?.exists() {
+                info!(
+                    "\"{}\" was decoded and cut already: Decoding skipped",
+                    self.file_name()
+                );
+                self.change_status(Status::Cut)?;
+                return Ok(true);
+            }
+
+            fs::rename(&archived_path, &decoded_path).with_context(|| {
+                format!(
+                    "Could not move \"{}\" from archive directory",
+                    archived_path.display()
+                )
+            })?;
+        }
+
+        info!(
+            "\"{}\" was decoded already: Decoding skipped",
+            self.file_name()
+        );
+
+        // Update video (status, path)
+        self.change_to_next_status()?;
+
+        Ok(true)
+    }
+
+    // Move decoded video to archive directory and write the journal of its
+    // processing next to it
+    fn move_to_archive_dir(&self, cutlist_ctrl: &CutlistCtrl) -> anyhow::Result<()> {
         // Nothing to do if video is not in status "decoded"
         if self.status() != Status::Decoded {
             return Ok(());
         }
 
-        if let Err(err) = fs::rename(
-            &self.p,
-            dirs::working_sub_dir(&DirKind::Archive)
-                .unwrap()
-                .join(self.file_name()),
-        ) {
+        let archived_path = dirs::working_sub_dir(&DirKind::Archive)
+            .unwrap()
+            .join(self.os_file_name());
+
+        if let Err(err) = fs::rename(&self.p, &archived_path) {
             error!(
                 "{:?}",
                 anyhow!(err)
                     .context("Could not move video to archive directory after successful cutting")
             );
+            return Ok(());
+        }
+
+        if let Err(err) = self.write_journal(&archived_path, &self.next_path()?, cutlist_ctrl) {
+            warn!("{:?}", err.context("Could not write journal of video"));
         }
 
         Ok(())
@@ -404,7 +638,7 @@ impl Video {
 
         let target_dir = dirs::working_sub_dir(&(self.status()).as_dir_kind())?;
 
-        let target_path = target_dir.join(self.file_name());
+        let target_path = target_dir.join(self.os_file_name());
 
         // Nothing to do if video is already in correct directory
         if source_dir == target_dir {
@@ -412,7 +646,13 @@ impl Video {
         }
 
         // Copy video file to working sub directory and adjust path
-        fs::rename(&self.p, &target_path)?;
+        fs::rename(&self.p, &target_path).with_context(|| {
+            format!(
+                "Could not move \"{}\" to \"{}\" (\"otr doctor\" checks the working directories)",
+                self.p.display(),
+                target_dir.display()
+            )
+        })?;
         self.p = target_path;
 
         Ok(())
@@ -422,29 +662,467 @@ impl Video {
     // decoded status if it is encoded now or the cut status if it is decoded
     // now. If the video is already cut, its current path is returned.
     fn next_path(&self) -> anyhow::Result<PathBuf> {
-        match self.s {
-            Status::Encoded => Ok(self
-                .p
-                .parent()
-                .unwrap()
-                .parent()
-                .unwrap()
-                .join(dirs::working_sub_dir(&(Status::Decoded).as_dir_kind())?)
-                .join(self.file_name())
-                .with_extension("")),
-            Status::Decoded => Ok(self
-                .p
-                .parent()
-                .unwrap()
-                .parent()
+        match self.s.next() {
+            Some(next_status) => self.path(next_status),
+            None => Ok(self.p.to_path_buf()),
+        }
+    }
+
+    /// Temporary path under which the cut video is written while the video is
+    /// being cut
+    fn partial_cut_path(&self) -> anyhow::Result<PathBuf> {
+        Ok(partial_cut_path(&self.path(Status::Cut)?))
+    }
+
+    /// Path the video has in status status: The file is stored in the working
+    /// sub directory of that status, and its name is derived from the current
+    /// file name (see file_name_in_status). For the current status, the current
+    /// path is returned. Since videos only move forward in the pipeline,
+    /// there is no path for an earlier status
+    fn path(&self, status: Status) -> anyhow::Result<PathBuf> {
+        if status == self.s {
+            return Ok(self.p.to_path_buf());
+        }
+        if status < self.s {
+            return Err(anyhow!(
+                "\"{}\" has no path in status {} since it is {} already",
+                self.file_name(),
+                status,
+                self.s
+            ));
+        }
+
+        Ok(
+            dirs::working_sub_dir(&status.as_dir_kind())?.join(file_name_in_status(
+                self.os_file_name(),
+                self.s,
+                status,
+            )),
+        )
+    }
+}
+
+/// Cuts the decoded video in_path and writes the cut video to out. The cut video
+/// is written under a temporary name and renamed when it is complete. Thereby,
+/// other applications (e.g., media center library scanners) never see a
+/// partially written cut video. If verify is true and the cut video is not
+/// plausible, it is removed (errors of verification and renaming are returned
+/// as CutError::Any)
+fn cut_via_partial(
+    in_path: &Path,
+    out: &Path,
+    cutlist_ctrl: &CutlistCtrl,
+    verify: bool,
+) -> Result<(), CutError> {
+    let partial_path = partial_cut_path(out);
+
+    let res = cutting::cut(in_path, &partial_path, cutlist_ctrl);
+    if let Err(CutError::Any(_) | CutError::Default | CutError::NoCutlist) = res {
+        remove_partial_cut(&partial_path);
+        return res;
+    }
+
+    if verify {
+        if let Err(err) = cutlist::kept_intervals(in_path, &cutlist_ctrl.access_type)
+            .and_then(|kept| probe::verify_cut(in_path, &partial_path, kept.as_deref()))
+        {
+            remove_partial_cut(&partial_path);
+            return Err(CutError::Any(
+                err.context("Cut video failed verification and was removed"),
+            ));
+        }
+    }
+    fs::rename(&partial_path, out)
+        .with_context(|| {
+            format!(
+                "Could not rename cut video \"{}\" to \"{}\"",
+                partial_path.display(),
+                out.display()
+            )
+        })
+        .map_err(CutError::Any)?;
+
+    res
+}
+
+/// Temporary path under which the cut video out is written while it is being
+/// cut
+fn partial_cut_path(out: &Path) -> PathBuf {
+    let mut file_name = OsString::from(dirs::PARTIAL_CUT_FILE_PREFIX);
+    file_name.push(out.file_name().unwrap_or_default());
+    out.with_file_name(file_name)
+}
+
+/// Removes a partially (or wrongly) cut video. Errors are only reported
+fn remove_partial_cut(partial_path: &Path) {
+    if partial_path.exists() {
+        if let Err(err) = fs::remove_file(partial_path) {
+            warn!(
+                "{:?}",
+                anyhow!(err).context(format!(
+                    "Could not remove partially cut video \"{}\"",
+                    partial_path.display()
+                ))
+            );
+        }
+    }
+}
+
+/// Converts an error of cutting a video into an error that is returned to the
+/// user
+fn cut_error(err: CutError) -> anyhow::Error {
+    match err {
+        CutError::CutlistSubmissionFailed(err) => {
+            err.context("Video was cut, but cut list could not be submitted to cutlist.at")
+        }
+        CutError::Any(err) => err.context("Could not cut video"),
+        CutError::Default => anyhow!("Could not cut video for an unknown reason"),
+        CutError::NoCutlist => anyhow!(ProcessingError::NoCutlist),
+    }
+}
+
+/// Converts a file name into a string, escaping each byte that is not part of
+/// a valid UTF-8 sequence as "/x<HEX>". Since file names cannot contain "/",
+/// different file names are always converted into different strings (in
+/// contrast to a lossy conversion)
+fn escaped_file_name(file_name: &OsStr) -> Cow<'_, str> {
+    if let Some(file_name) = file_name.to_str() {
+        return Cow::Borrowed(file_name);
+    }
+
+    let mut escaped = String::new();
+    for chunk in file_name.as_encoded_bytes().utf8_chunks() {
+        escaped.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("/x{:02X}", byte));
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Derives the file name a video has in status to from its file name in status
+/// from, stage by stage: Decoding removes the extension ".otrkey", cutting
+/// inserts "cut." before the extension. Only the extension is touched, thus
+/// the rest of the file name is kept as is (even if it is not valid UTF-8)
+fn file_name_in_status(file_name: &OsStr, from: Status, to: Status) -> PathBuf {
+    let mut file_name = PathBuf::from(file_name);
+    let mut current = from;
+    while current < to {
+        file_name = match current {
+            Status::Encoded => file_name.with_extension(""),
+            _ => {
+                let mut extension = OsString::from("cut.");
+                extension.push(file_name.extension().unwrap_or_default());
+                file_name.with_extension(extension)
+            }
+        };
+        current = current.next().unwrap();
+    }
+    file_name
+}
+
+/// Calculates the MD5 checksum of a file
+fn md5_checksum(path: &Path) -> anyhow::Result<String> {
+    let mut hasher = Md5::new();
+    io::copy(
+        &mut fs::File::open(path)
+            .with_context(|| format!("Could not open \"{}\"", path.display()))?,
+        &mut hasher,
+    )
+    .with_context(|| format!("Could not calculate checksum of \"{}\"", path.display()))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashSet, os::unix::ffi::OsStrExt};
+
+    /// Name of an encoded video whose title contains a Latin-1 encoded umlaut
+    /// (i.e., the file name is not valid UTF-8)
+    const NON_UTF8_FILE_NAME: &[u8] =
+        b"Sp\xe4tshow_24.01.31_23-35_ard_45_TVOON_DE.mpg.HQ.avi.otrkey";
+
+    /// Creates an empty file in a test-specific temporary directory and returns
+    /// its path
+    fn create_file(test: &str, file_name: &OsStr) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("otr-test-{}-{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(file_name);
+        fs::File::create(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn new_accepts_non_utf8_file_name() {
+        let file_name = OsStr::from_bytes(NON_UTF8_FILE_NAME);
+        let path = create_file("non-utf8", file_name);
+
+        let video = Video::new(&path).unwrap();
+        assert!(video.status() == Status::Encoded);
+        assert_eq!(video.os_file_name(), file_name);
+        assert!(video.file_name().starts_with("Sp\u{FFFD}tshow_"));
+        assert_eq!(
+            video.key().to_string(),
+            "Sp/xE4tshow_24.01.31_23-35_ard_45_TVOON_DE.HQ.avi"
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn new_creates_different_keys_for_different_non_utf8_file_names() {
+        let path_e4 = create_file("non-utf8-e4", OsStr::from_bytes(NON_UTF8_FILE_NAME));
+        let path_fc = create_file(
+            "non-utf8-fc",
+            OsStr::from_bytes(b"Sp\xfctshow_24.01.31_23-35_ard_45_TVOON_DE.mpg.HQ.avi.otrkey"),
+        );
+
+        let (video_e4, video_fc) = (Video::new(&path_e4).unwrap(), Video::new(&path_fc).unwrap());
+        assert_eq!(video_e4.file_name(), video_fc.file_name());
+        assert!(video_e4.key() != video_fc.key());
+
+        fs::remove_dir_all(path_e4.parent().unwrap()).unwrap();
+        fs::remove_dir_all(path_fc.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn new_accepts_exotic_file_names() {
+        for file_name in [
+            "Die Sendung mit der Maus_24.02.04_09-30_ard_30_TVOON_DE.mpg.HD.avi",
+            "Café-Geschichten 🎬_24.02.04_20-15_arte_90_TVOON_DE.mpg.avi",
+            "L'été – «Ça va»_24.02.04_22-00_zdf_60_TVOON_DE.mpg.HQ.cut.avi",
+        ] {
+            let path = create_file("exotic", OsStr::new(file_name));
+
+            let video = Video::new(&path).unwrap();
+            assert_eq!(video.file_name(), file_name);
+
+            fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn recorded_at_is_parsed_from_key() {
+        let path = create_file(
+            "recorded-at",
+            OsStr::new("Tatort_23.12.31_20-15_ard_90_TVOON_DE.mpg.HD.avi.otrkey"),
+        );
+
+        assert_eq!(
+            Video::new(&path).unwrap().recorded_at(),
+            NaiveDate::from_ymd_opt(2023, 12, 31)
                 .unwrap()
-                .join(dirs::working_sub_dir(&(Status::Cut).as_dir_kind())?)
-                .join(self.file_name())
-                .with_extension(format!(
-                    "cut.{}",
-                    self.p.extension().unwrap().to_str().unwrap()
-                ))),
-            _ => Ok(self.p.to_path_buf()),
+                .and_hms_opt(20, 15, 0)
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn recorded_at_is_none_for_invalid_dates() {
+        let path = create_file(
+            "invalid-date",
+            OsStr::new("Tatort_24.02.30_25-15_ard_90_TVOON_DE.mpg.HD.avi"),
+        );
+
+        assert_eq!(Video::new(&path).unwrap().recorded_at(), None);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn new_rejects_non_video_files() {
+        let path = create_file("no-video", OsStr::from_bytes(b"notes-\xff.txt"));
+
+        assert!(Video::new(&path).is_err());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn tag_key_must_be_known() {
+        let file_name = "Tatort_23.12.31_20-15_ard_90_TVOON_DE.mpg.HD.avi";
+        let path = create_file("tag-key", OsStr::new(file_name));
+        let key = "Tatort_23.12.31_20-15_ard_90_TVOON_DE.HD.avi";
+        let known_keys = HashSet::from([key.to_string()]);
+
+        assert_eq!(
+            tagging::key(path.to_str().unwrap(), &HashSet::new()).unwrap(),
+            key
+        );
+        assert_eq!(tagging::key(key, &known_keys).unwrap(), key);
+        // Typo in the key
+        assert!(tagging::key("Tatrot_23.12.31_20-15_ard_90_TVOON_DE.HD.avi", &known_keys).is_err());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn own_files_are_recognized() {
+        assert!(dirs::is_own_file(OsStr::new(dirs::LOCK_FILE_NAME)));
+        assert!(dirs::is_own_file(OsStr::new(dirs::RETRY_FILE_NAME)));
+        assert!(dirs::is_own_file(OsStr::new(dirs::TAGS_FILE_NAME)));
+        assert!(dirs::is_own_file(OsStr::new(dirs::REPORT_FILE_NAME)));
+        assert!(!dirs::is_own_file(OsStr::new(
+            "Tatort_23.12.31_20-15_ard_90_TVOON_DE.mpg.HD.avi.otrkey"
+        )));
+    }
+
+    #[test]
+    fn file_name_in_status_keeps_non_utf8_bytes() {
+        let file_name = OsStr::from_bytes(NON_UTF8_FILE_NAME);
+
+        assert_eq!(
+            file_name_in_status(file_name, Status::Encoded, Status::Decoded).as_os_str(),
+            OsStr::from_bytes(b"Sp\xe4tshow_24.01.31_23-35_ard_45_TVOON_DE.mpg.HQ.avi")
+        );
+        assert_eq!(
+            file_name_in_status(file_name, Status::Encoded, Status::Cut).as_os_str(),
+            OsStr::from_bytes(b"Sp\xe4tshow_24.01.31_23-35_ard_45_TVOON_DE.mpg.HQ.cut.avi")
+        );
+    }
+
+    #[test]
+    fn new_accepts_audio_recordings() {
+        for (file_name, status) in [
+            (
+                "Radiofeature_24.03.01_20-05_dlf_55_TVOON_DE.mp3.otrkey",
+                Status::Encoded,
+            ),
+            (
+                "Radiofeature_24.03.01_20-05_dlf_55_TVOON_DE.mp3",
+                Status::Decoded,
+            ),
+            (
+                "Radiofeature_24.03.01_20-05_dlf_55_TVOON_DE.cut.mp3",
+                Status::Cut,
+            ),
+        ] {
+            let path = create_file("audio", OsStr::new(file_name));
+
+            let video = Video::new(&path).unwrap();
+            assert!(video.status() == status);
+            assert_eq!(
+                video.key().to_string(),
+                "Radiofeature_24.03.01_20-05_dlf_55_TVOON_DE.mp3"
+            );
+
+            fs::remove_dir_all(path.parent().unwrap()).unwrap();
         }
     }
+
+    #[test]
+    fn file_name_in_status_of_audio_recording() {
+        let file_name = OsStr::new("Radiofeature_24.03.01_20-05_dlf_55_TVOON_DE.ac3.otrkey");
+
+        assert_eq!(
+            file_name_in_status(file_name, Status::Encoded, Status::Decoded).as_os_str(),
+            "Radiofeature_24.03.01_20-05_dlf_55_TVOON_DE.ac3"
+        );
+        assert_eq!(
+            file_name_in_status(file_name, Status::Encoded, Status::Cut).as_os_str(),
+            "Radiofeature_24.03.01_20-05_dlf_55_TVOON_DE.cut.ac3"
+        );
+    }
+
+    #[test]
+    fn retry_queue_holds_videos_without_cut_list() {
+        let path = create_file(
+            "retry-queue",
+            OsStr::new("Tatort_24.01.28_20-15_ard_90_TVOON_DE.mpg.HQ.avi"),
+        );
+        let mut video = Video::new(&path).unwrap();
+        let mut queue = RetryQueue::default();
+        let retry_after = Duration::from_secs(3600);
+
+        assert!(queue.failed_recently(&video, retry_after).is_none());
+
+        video.e = Some(anyhow!(ProcessingError::NoCutlist));
+        queue.update(&video);
+        assert!(queue.failed_recently(&video, retry_after).is_some());
+        // After retry_after, the video is retried
+        assert!(queue.failed_recently(&video, Duration::ZERO).is_none());
+
+        // A video that is still decoded (e.g., since cutting was deferred) stays
+        // in the queue
+        video.e = None;
+        queue.update(&video);
+        assert!(queue.failed_recently(&video, retry_after).is_some());
+
+        // A video that failed for another reason is removed from the queue
+        video.e = Some(anyhow!("Could not cut video"));
+        queue.update(&video);
+        video.e = None;
+        assert!(queue.failed_recently(&video, retry_after).is_none());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn retry_queue_ignores_videos_that_are_not_decoded() {
+        let path = create_file(
+            "retry-queue-status",
+            OsStr::new("Tatort_24.01.28_20-15_ard_90_TVOON_DE.mpg.HQ.avi.otrkey"),
+        );
+        let mut video = Video::new(&path).unwrap();
+        let mut queue = RetryQueue::default();
+
+        video.e = Some(anyhow!(ProcessingError::NoCutlist));
+        queue.update(&video);
+        assert!(queue
+            .failed_recently(&video, Duration::from_secs(3600))
+            .is_none());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn retry_queue_is_empty_if_retry_file_is_missing() {
+        let dir =
+            std::env::temp_dir().join(format!("otr-test-retry-missing-{}", std::process::id()));
+
+        assert!(RetryQueue::load_from(&dir.join(dirs::RETRY_FILE_NAME)).is_ok());
+    }
+
+    #[test]
+    fn retry_queue_is_not_loaded_from_corrupt_retry_file() {
+        let path = create_file("retry-corrupt", OsStr::new(dirs::RETRY_FILE_NAME));
+        fs::write(&path, "{\"no_cutlist\": [").unwrap();
+
+        assert!(RetryQueue::load_from(&path).is_err());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn retry_queue_is_saved_and_loaded() {
+        let path = create_file(
+            "retry-save",
+            OsStr::new("Tatort_24.01.28_20-15_ard_90_TVOON_DE.mpg.HQ.avi"),
+        );
+        let mut video = Video::new(&path).unwrap();
+        video.e = Some(anyhow!(ProcessingError::NoCutlist));
+        let mut queue = RetryQueue::default();
+        queue.update(&video);
+
+        let retry_file = path.with_file_name(dirs::RETRY_FILE_NAME);
+        queue.save_to(&retry_file).unwrap();
+        let queue = RetryQueue::load_from(&retry_file).unwrap();
+        assert!(queue
+            .failed_recently(&video, Duration::from_secs(3600))
+            .is_some());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn file_name_in_status_of_current_status_is_unchanged() {
+        let file_name = OsStr::new("Film_24.01.31_20-15_zdf_90_TVOON_DE.mpg.HD.avi");
+
+        assert_eq!(
+            file_name_in_status(file_name, Status::Decoded, Status::Decoded).as_os_str(),
+            file_name
+        );
+    }
 }
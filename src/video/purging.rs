@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::dirs::{self, RETRY_FILE_NAME};
+use crate::cfg;
+
+use anyhow::{anyhow, Context};
+use log::*;
+use std::fs;
+
+/// Removes personal data and caches, e.g. before a machine is handed over to
+/// someone else:
+/// - credentials: OTR user and password and the access token for cutlist.at are
+///   removed from the configuration file
+/// - caches: The directory for temporary files is removed (i.e., cutting
+///   directories, generated cut list files and crash reports)
+/// - state: The retry queue in the working directory is removed. The tags of
+///   the videos are no state but user data, thus they are kept
+///
+/// Videos, their journals and all other settings are kept
+pub fn purge(credentials: bool, caches: bool, state: bool) -> anyhow::Result<()> {
+    if credentials {
+        if cfg::remove_credentials()? {
+            info!("Removed credentials from configuration file");
+        } else {
+            info!("No credentials stored in configuration file");
+        }
+    }
+
+    if caches {
+        let tmp_dir = dirs::tmp_dir()
+            .ok_or_else(|| anyhow!("Could not determine directory for temporary files"))?;
+        if tmp_dir.is_dir() {
+            fs::remove_dir_all(&tmp_dir)
+                .with_context(|| format!("Could not remove \"{}\"", tmp_dir.display()))?;
+            info!("Removed \"{}\"", tmp_dir.display());
+        }
+    }
+
+    if state {
+        let path = dirs::working_dir()?.join(RETRY_FILE_NAME);
+        if path.is_file() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Could not remove \"{}\"", path.display()))?;
+            info!("Removed \"{}\"", path.display());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{
+    dirs::{self, DirKind, REPORT_FILE_NAME},
+    Status, Video,
+};
+use crate::probe;
+
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Style sheet of the report. It is embedded to keep the report self-contained
+const STYLE: &str = "body{font-family:sans-serif;margin:1em}\
+    table{border-collapse:collapse;width:100%}\
+    th,td{border:1px solid #ccc;padding:.4em;text-align:left;vertical-align:top}\
+    th{background:#eee}.failed{color:#b00}pre{white-space:pre-wrap}";
+
+/// Report about a run of otr, i.e. about the videos that were processed. It can
+/// be written as self-contained HTML file into the working directory, and be
+/// summarized for notifications
+pub struct Report {
+    started_at: DateTime<Local>,
+    entries: Vec<Entry>,
+}
+
+/// Result of the processing of a video
+struct Entry {
+    file_name: String,
+    /// Status before and after processing
+    from: Status,
+    to: Status,
+    processing_time: Duration,
+    /// Path of the video after processing
+    path: PathBuf,
+    error: Option<String>,
+    /// Whether the video could not be cut since there was no cut list for it
+    lacks_cutlist: bool,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Report {
+            started_at: Local::now(),
+            entries: vec![],
+        }
+    }
+
+    /// Adds the result of the processing of a video. from is its status before
+    /// processing. Videos whose status did not change and that have no error
+    /// (e.g., since they were processed already) are not reported
+    pub fn add(&mut self, video: &Video, from: Status, processing_time: Duration) {
+        if from == video.status() && video.error().is_none() {
+            return;
+        }
+
+        self.entries.push(Entry {
+            file_name: video.file_name().into_owned(),
+            from,
+            to: video.status(),
+            processing_time,
+            path: video.p.clone(),
+            error: video.error().as_ref().map(|err| format!("{:?}", err)),
+            lacks_cutlist: video.lacks_cutlist(),
+        });
+    }
+
+    /// Summary of the report (numbers of decoded, cut and failed videos, and of
+    /// videos without cut list). If no video was decoded or cut, and none
+    /// failed, None is returned
+    pub fn summary(&self) -> Option<String> {
+        let count = |is_counted: fn(&Entry) -> bool| {
+            self.entries
+                .iter()
+                .filter(|entry| is_counted(entry))
+                .count()
+        };
+        let decoded = count(|entry| entry.from == Status::Encoded && entry.to != Status::Encoded);
+        let cut = count(|entry| entry.from != Status::Cut && entry.to == Status::Cut);
+        let failed = count(|entry| entry.error.is_some() && !entry.lacks_cutlist);
+        let no_cutlist = count(|entry| entry.lacks_cutlist);
+
+        if decoded + cut + failed == 0 {
+            return None;
+        }
+
+        Some(format!(
+            "Decoded: {}, cut: {}, failed: {}, without cut list: {}",
+            decoded, cut, failed, no_cutlist
+        ))
+    }
+
+    /// Writes the report into the working directory (an existing report is
+    /// overwritten) and returns its path
+    pub fn write(&self) -> anyhow::Result<PathBuf> {
+        let path = dirs::working_dir()?.join(REPORT_FILE_NAME);
+        fs::write(&path, self.html()?)
+            .with_context(|| format!("Could not write report \"{}\"", path.display()))?;
+        Ok(path)
+    }
+
+    /// Renders the report as HTML
+    fn html(&self) -> anyhow::Result<String> {
+        let title = format!("otr report of {}", self.started_at.format("%Y-%m-%d %H:%M"));
+        let failed = self
+            .entries
+            .iter()
+            .filter(|entry| entry.error.is_some())
+            .count();
+
+        let mut html = String::new();
+        write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+             <h1>{title}</h1>\n<p>{} video(s) processed, {} failed</p>\n",
+            self.entries.len(),
+            failed
+        )?;
+
+        if !self.entries.is_empty() {
+            html.push_str(
+                "<table>\n<tr><th>Video</th><th>Status</th><th>Duration</th>\
+                 <th>Removed</th><th>Result</th></tr>\n",
+            );
+            for (i, entry) in self.entries.iter().enumerate() {
+                writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape(&entry.file_name),
+                    if entry.from == entry.to {
+                        entry.to.to_string()
+                    } else {
+                        format!("{} &rarr; {}", entry.from, entry.to)
+                    },
+                    duration_string(entry.processing_time.as_secs()),
+                    if entry.from != Status::Cut && entry.to == Status::Cut {
+                        removed_time(&entry.path).map_or(String::new(), |removed| {
+                            format!("{:.1} min", removed / 60.0)
+                        })
+                    } else {
+                        String::new()
+                    },
+                    if entry.error.is_some() {
+                        format!("<a class=\"failed\" href=\"#error-{}\">Failed</a>", i)
+                    } else {
+                        "OK".to_string()
+                    }
+                )?;
+            }
+            html.push_str("</table>\n");
+        }
+
+        if failed > 0 {
+            html.push_str("<h2>Errors</h2>\n");
+            for (i, entry) in self.entries.iter().enumerate() {
+                if let Some(error) = &entry.error {
+                    write!(
+                        html,
+                        "<h3 id=\"error-{}\">{}</h3>\n<pre>{}</pre>\n",
+                        i,
+                        escape(&entry.file_name),
+                        escape(error)
+                    )?;
+                }
+            }
+        }
+
+        html.push_str("</body>\n</html>\n");
+
+        Ok(html)
+    }
+}
+
+/// Time (in seconds) that was removed from a cut video, i.e. the difference of
+/// the durations of the archived decoded video and the cut video. If a duration
+/// cannot be determined, None is returned
+fn removed_time(cut_path: &Path) -> Option<f64> {
+    // The file name of the decoded video is the file name of the cut video
+    // without "cut." before the extension
+    let mut file_name = Path::new(cut_path.file_stem()?)
+        .with_extension("")
+        .into_os_string();
+    file_name.push(".");
+    file_name.push(cut_path.extension()?);
+    let archived_path = dirs::working_sub_dir(&DirKind::Archive)
+        .ok()?
+        .join(file_name);
+
+    Some(probe::duration(&archived_path).ok()? - probe::duration(cut_path).ok()?)
+}
+
+/// Formats a number of seconds as [H:]MM:SS
+fn duration_string(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Escapes the characters that have a special meaning in HTML
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,110 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{
+    dirs::{self, RETRY_FILE_NAME},
+    ProcessingError, Status, Video,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, SecondsFormat};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Videos that could not be cut since there was no cut list for them, with the
+/// point in time when that happened. The queue is persisted in the working
+/// directory, so that subsequent runs can skip videos that failed recently
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub struct RetryQueue {
+    /// Point in time (RFC 3339) per video key
+    no_cutlist: HashMap<String, String>,
+}
+
+impl RetryQueue {
+    /// Reads the retry queue from the working directory. If there is no retry
+    /// file yet, an empty queue is returned
+    pub fn load() -> anyhow::Result<Self> {
+        Self::load_from(&path()?)
+    }
+
+    /// Stores the retry queue in the working directory
+    pub fn save(&self) -> anyhow::Result<()> {
+        self.save_to(&path()?)
+    }
+
+    /// Reads the retry queue from the retry file at path. If the file does not
+    /// exist, an empty queue is returned
+    pub(super) fn load_from(path: &Path) -> anyhow::Result<Self> {
+        match File::open(path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))
+                .with_context(|| format!("Could not read retry file \"{}\"", path.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(RetryQueue::default()),
+            Err(err) => Err(err)
+                .with_context(|| format!("Could not open retry file \"{}\"", path.display())),
+        }
+    }
+
+    /// Stores the retry queue in the retry file at path
+    pub(super) fn save_to(&self, path: &Path) -> anyhow::Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Could not create retry file \"{}\"", path.display()))?;
+        serde_json::to_writer_pretty(file, self)
+            .with_context(|| format!("Could not write retry file \"{}\"", path.display()))
+    }
+
+    /// Returns the point in time when the cutting of a decoded video failed
+    /// since there was no cut list for it, if that was less than retry_after
+    /// ago. Otherwise, None is returned
+    pub fn failed_recently(&self, video: &Video, retry_after: Duration) -> Option<DateTime<Local>> {
+        if video.status() != Status::Decoded {
+            return None;
+        }
+
+        let failed_at = DateTime::parse_from_rfc3339(self.no_cutlist.get(&video.key().0)?)
+            .ok()?
+            .with_timezone(&Local);
+        if Local::now()
+            .signed_duration_since(failed_at)
+            .to_std()
+            .ok()?
+            < retry_after
+        {
+            Some(failed_at)
+        } else {
+            None
+        }
+    }
+
+    /// Updates the queue after a video was processed: It is added if it could
+    /// not be cut since there was no cut list for it, and removed if it was cut
+    /// or failed for another reason
+    pub fn update(&mut self, video: &Video) {
+        if video.lacks_cutlist() {
+            self.no_cutlist.insert(
+                video.key().0.clone(),
+                Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            );
+        } else if video.status() != Status::Decoded || video.error().is_some() {
+            self.no_cutlist.remove(&video.key().0);
+        }
+    }
+}
+
+impl Video {
+    /// Returns true if the video could not be cut since there was no cut list
+    /// for it
+    pub fn lacks_cutlist(&self) -> bool {
+        matches!(self.processing_error(), Some(ProcessingError::NoCutlist))
+    }
+}
+
+/// Path of the retry file
+fn path() -> anyhow::Result<PathBuf> {
+    Ok(dirs::working_dir()?.join(RETRY_FILE_NAME))
+}
@@ -0,0 +1,71 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{dirs::SYNCED_FILE_PREFIX, Video};
+use crate::probe;
+
+use anyhow::{anyhow, Context};
+use log::*;
+use std::{ffi::OsString, fs, process::Command, time::Duration};
+
+impl Video {
+    /// Measures the constant offset between audio and video of a decoded video
+    /// (i.e., the difference of the start times of the first audio and the first
+    /// video stream) and corrects it if it exceeds the threshold. For that, the
+    /// audio streams are shifted accordingly. The corrected video is written
+    /// under a temporary name and replaces the decoded video when it is complete.
+    /// Videos without audio or video stream are not changed
+    pub fn correct_av_offset(&self, threshold: Duration) -> anyhow::Result<()> {
+        let offset = match probe::av_offset(&self.p)? {
+            Some(_offset) => _offset,
+            None => return Ok(()),
+        };
+        if offset.abs() <= threshold.as_secs_f64() {
+            trace!(
+                "A/V offset of \"{}\" ({:.3}s) is within threshold",
+                self.file_name(),
+                offset
+            );
+            return Ok(());
+        }
+
+        info!(
+            "Correcting A/V offset of \"{}\" ({:.3}s) ...",
+            self.file_name(),
+            offset
+        );
+
+        let mut synced_file_name = OsString::from(SYNCED_FILE_PREFIX);
+        synced_file_name.push(self.os_file_name());
+        let synced_path = self.p.with_file_name(synced_file_name);
+
+        // All streams except audio are taken from the first input, the audio
+        // streams from the second input, whose timestamps are shifted
+        let output = Command::new("ffmpeg")
+            .args(["-hide_banner", "-nostats", "-v", "error", "-y", "-i"])
+            .arg(&self.p)
+            .args(["-itsoffset", &format!("{:.6}", -offset), "-i"])
+            .arg(&self.p)
+            .args(["-map", "0", "-map", "-0:a", "-map", "1:a", "-c", "copy"])
+            .arg(&synced_path)
+            .output()
+            .context("Could not execute ffmpeg")?;
+        if !output.status.success() {
+            let _ = fs::remove_file(&synced_path);
+            return Err(anyhow!(
+                "Could not shift audio of \"{}\": {}",
+                self.file_name(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        fs::rename(&synced_path, &self.p).with_context(|| {
+            format!(
+                "Could not rename \"{}\" to \"{}\"",
+                synced_path.display(),
+                self.p.display()
+            )
+        })
+    }
+}
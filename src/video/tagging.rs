@@ -0,0 +1,149 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{
+    dirs::{self, DirKind, TAGS_FILE_NAME},
+    Video,
+};
+
+use anyhow::{anyhow, Context};
+use log::*;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+/// Free-form tags of videos (e.g., "comedy" or "keep-forever") per video key.
+/// Since decoded and cut videos have the same key, the tags apply to both. The
+/// tags are persisted in the working directory
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+pub struct Tags {
+    tags: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Tags {
+    /// Reads the tags from the working directory. If there is no tags file yet,
+    /// no video has tags
+    pub fn load() -> anyhow::Result<Self> {
+        let path = path()?;
+        match File::open(&path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))
+                .with_context(|| format!("Could not read tags file \"{}\"", path.display())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Tags::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("Could not open tags file \"{}\"", path.display()))
+            }
+        }
+    }
+
+    /// Stores the tags in the working directory
+    fn save(&self) -> anyhow::Result<()> {
+        let path = path()?;
+        let file = File::create(&path)
+            .with_context(|| format!("Could not create tags file \"{}\"", path.display()))?;
+        serde_json::to_writer_pretty(file, self)
+            .with_context(|| format!("Could not write tags file \"{}\"", path.display()))
+    }
+
+    /// Keys of the videos that have the given tag
+    pub fn keys_with(&self, tag: &str) -> HashSet<String> {
+        self.tags
+            .iter()
+            .filter(|(_, tags)| tags.contains(tag))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+}
+
+/// Adds tags to a video or - if remove is true - removes them from it. The
+/// video can be given by its key or by the path of one of its files. If no tags
+/// are given, the tags of the video are printed
+pub fn tag(video: &str, tags: &[String], remove: bool) -> anyhow::Result<()> {
+    let mut all_tags = Tags::load()?;
+
+    // Keys of videos that were deleted meanwhile are still known if they have
+    // tags, so that these tags can be listed and removed
+    let mut known_keys = video_keys()?;
+    known_keys.extend(all_tags.tags.keys().cloned());
+    let key = key(video, &known_keys)?;
+
+    if tags.is_empty() {
+        for tag in all_tags.tags.get(&key).into_iter().flatten() {
+            println!("{}", tag);
+        }
+        return Ok(());
+    }
+
+    let video_tags = all_tags.tags.entry(key.clone()).or_default();
+    if remove {
+        for tag in tags {
+            video_tags.remove(tag);
+        }
+        if video_tags.is_empty() {
+            all_tags.tags.remove(&key);
+        }
+    } else {
+        video_tags.extend(tags.iter().cloned());
+    }
+    all_tags.save()?;
+
+    info!(
+        "{} tag(s) {} \"{}\"",
+        if remove { "Removed" } else { "Added" },
+        if remove { "from" } else { "to" },
+        key
+    );
+
+    Ok(())
+}
+
+/// Determines the key of video, which is either the path of a video file or a
+/// key. In the latter case, it must be contained in known_keys. Thereby, typos
+/// do not silently lead to tags that never match
+pub(super) fn key(video: &str, known_keys: &HashSet<String>) -> anyhow::Result<String> {
+    if let Ok(_video) = Video::new(Path::new(video)) {
+        return Ok(_video.key().0.clone());
+    }
+    if known_keys.contains(video) {
+        return Ok(video.to_string());
+    }
+    Err(anyhow!(
+        "\"{}\" is neither the path of a video nor the key of a video in the working directory",
+        video
+    ))
+}
+
+/// Keys of the videos in the working (sub) directories
+fn video_keys() -> anyhow::Result<HashSet<String>> {
+    let mut keys = HashSet::new();
+
+    for dir_kind in [
+        DirKind::Root,
+        DirKind::Encoded,
+        DirKind::Decoded,
+        DirKind::Cut,
+        DirKind::Archive,
+    ] {
+        let dir = dirs::working_sub_dir(&dir_kind)?;
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(dir)
+            .with_context(|| format!("Could not read directory \"{}\"", dir.display()))?
+        {
+            if let Ok(video) = Video::new(&entry?.path()) {
+                keys.insert(video.key().0.clone());
+            }
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Path of the tags file
+fn path() -> anyhow::Result<PathBuf> {
+    Ok(dirs::working_dir()?.join(TAGS_FILE_NAME))
+}
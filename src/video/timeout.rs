@@ -0,0 +1,166 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{
+    dirs::{self, CUTTING_DIR_PREFIX, FFMS2_INDEX_EXTS},
+    ProcessingError, Status, Video,
+};
+
+use anyhow::{anyhow, Context};
+use log::*;
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    os::unix::process::CommandExt,
+    path::PathBuf,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Interval in which it is checked whether the child process has finished
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+impl Video {
+    /// Decode and cut a video in a child process, which is otr called with
+    /// child_args and the path of the video, and gets the additional
+    /// environment variables child_env. If that takes longer than
+    /// timeout, the child process and all processes it started (e.g., ffmpeg)
+    /// are killed, temporary artefacts are removed and the video gets a
+    /// ProcessingError::Timeout error. If the child process fails, its exit code
+    /// is mapped to the corresponding processing error via error_of_exit_code.
+    /// The video status and path is updated according to the progress the child
+    /// process made.
+    pub fn process_with_timeout(
+        &mut self,
+        child_args: &[OsString],
+        child_env: &[(&str, &str)],
+        timeout: Duration,
+        error_of_exit_code: fn(i32) -> Option<ProcessingError>,
+    ) {
+        if let Err(err) =
+            self._process_with_timeout(child_args, child_env, timeout, error_of_exit_code)
+        {
+            self.e = Some(err)
+        }
+    }
+
+    /// Decode and cut a video in a child process (private function which is
+    /// wrapped by its public counterpart)
+    fn _process_with_timeout(
+        &mut self,
+        child_args: &[OsString],
+        child_env: &[(&str, &str)],
+        timeout: Duration,
+        error_of_exit_code: fn(i32) -> Option<ProcessingError>,
+    ) -> anyhow::Result<()> {
+        // Nothing to do if video is cut already
+        if self.status() == Status::Cut {
+            return Ok(());
+        }
+
+        let mut child =
+            Command::new(env::current_exe().context("Could not determine path of otr executable")?)
+                .args(child_args)
+                .arg(&self.p)
+                .envs(child_env.iter().copied())
+                .stdin(Stdio::null())
+                // The child gets its own process group to be able to kill it together
+                // with the processes it started
+                .process_group(0)
+                .spawn()
+                .context("Could not start child process to process video")?;
+
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if let Some(exit_status) = child.try_wait()? {
+                self.adjust_to_files()?;
+
+                if !exit_status.success() {
+                    // Specific errors (e.g., a missing cut list) are handed over
+                    // from the child process via its exit code
+                    return Err(match exit_status.code().and_then(error_of_exit_code) {
+                        Some(err) => anyhow!(err),
+                        None => anyhow!("Child process could not process video"),
+                    });
+                }
+                return Ok(());
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        warn!(
+            "Processing of \"{}\" takes longer than {:?}: Abort",
+            self.file_name(),
+            timeout
+        );
+
+        // Kill the process group of the child process. Since the process group
+        // ID equals the process ID of the child, that is done by sending the
+        // signal to the negative process ID
+        unsafe {
+            libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+        }
+        child
+            .wait()
+            .context("Could not wait for termination of child process")?;
+
+        self.adjust_to_files()?;
+        self.remove_tmp_artefacts()?;
+
+        Err(anyhow!(ProcessingError::Timeout(timeout)))
+    }
+
+    /// Adjusts status and path of the video to the progress that the child
+    /// process made. The result of a processing step is only complete if the
+    /// file of the previous status was removed (encoded videos are removed
+    /// after successful decoding, decoded videos are moved to the archive
+    /// after successful cutting)
+    fn adjust_to_files(&mut self) -> anyhow::Result<()> {
+        while self.status() != Status::Cut && !self.p.exists() && self.next_path()?.exists() {
+            self.change_to_next_status()?;
+        }
+        Ok(())
+    }
+
+    /// Removes the incomplete result of an aborted processing step and the
+    /// temporary artefacts of cutting
+    fn remove_tmp_artefacts(&self) -> anyhow::Result<()> {
+        let mut artefacts: Vec<PathBuf> = vec![self.next_path()?];
+
+        if self.status() == Status::Decoded {
+            artefacts.push(self.partial_cut_path()?);
+            if let Some(tmp_dir) = dirs::tmp_dir() {
+                artefacts.push(tmp_dir.join(format!(
+                    "{}-{}",
+                    CUTTING_DIR_PREFIX,
+                    self.file_name()
+                )));
+            }
+            for ext in FFMS2_INDEX_EXTS {
+                artefacts.push(PathBuf::from(format!("{}.{}", self.p.display(), ext)));
+            }
+        }
+
+        for artefact in artefacts.iter().filter(|path| path.exists()) {
+            let res = if artefact.is_dir() {
+                fs::remove_dir_all(artefact)
+            } else {
+                fs::remove_file(artefact)
+            };
+            if let Err(err) = res {
+                warn!(
+                    "Could not remove \"{}\": {:?}",
+                    artefact.display(),
+                    anyhow!(err)
+                );
+                continue;
+            }
+            trace!("Removed \"{}\"", artefact.display());
+        }
+
+        Ok(())
+    }
+}
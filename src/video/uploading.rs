@@ -0,0 +1,65 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{Status, Video};
+use crate::cfg::Upload;
+
+use anyhow::{anyhow, Context};
+use log::*;
+use std::process::Command;
+
+/// Tool that is used to upload videos
+pub const UPLOAD_TOOL: &str = "rclone";
+
+impl Video {
+    /// Upload a cut video to the rclone remote that is configured for it. The
+    /// real thing is done by _upload, the private counterpart function
+    pub fn upload(&mut self, upload: &Upload) {
+        if let Err(err) = self._upload(upload) {
+            self.e = Some(err)
+        }
+    }
+
+    /// Upload a cut video (private upload function which is wrapped by its
+    /// public counterpart)
+    fn _upload(&self, upload: &Upload) -> anyhow::Result<()> {
+        // Nothing to do if video is not in status "cut"
+        if self.status() != Status::Cut {
+            return Ok(());
+        }
+
+        let remote = if let Some(_remote) = upload.remote(&self.file_name()) {
+            _remote
+        } else {
+            trace!("No upload remote configured for \"{}\"", self.file_name());
+            return Ok(());
+        };
+
+        info!("Uploading \"{}\" to \"{}\" ...", self.file_name(), remote);
+
+        let mut cmd = Command::new(UPLOAD_TOOL);
+        cmd.arg("copy").arg(&self.p).arg(remote);
+        if let Some(bandwidth_limit) = upload.bandwidth_limit() {
+            cmd.args(["--bwlimit", bandwidth_limit]);
+        }
+        if let Some(retries) = upload.retries() {
+            cmd.args(["--retries", &retries.to_string()]);
+        }
+
+        let output = cmd
+            .output()
+            .with_context(|| format!("Could not execute {}", UPLOAD_TOOL))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Video was cut, but could not be uploaded to \"{}\": {}",
+                remote,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        info!("Uploaded \"{}\"", self.file_name());
+
+        Ok(())
+    }
+}
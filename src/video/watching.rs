@@ -0,0 +1,161 @@
+// SPDX-FileCopyrightText: 2022-2024 Michael Picht <mipi@fsfe.org>
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::{
+    collecting,
+    dirs::{self, DirKind},
+    Status, Video,
+};
+
+use anyhow::Context;
+use itertools::{Either, Itertools};
+use log::*;
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs, thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Interval in which the working directories are checked for new encoded
+/// videos
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Size and modification time of a file. If one of them changes, the file is
+/// still being written (e.g., downloaded)
+#[derive(PartialEq)]
+struct FileState {
+    len: u64,
+    modified: SystemTime,
+}
+
+/// Watches the working directory and its sub directory for encoded videos and
+/// determines when the videos are to be processed
+pub struct Watcher {
+    /// Time an encoded video file must not have changed to be processed
+    debounce: Duration,
+    /// Time after which videos are processed again (e.g., since there were no
+    /// cut lists for them before)
+    retry_interval: Duration,
+    /// Encoded video files that were found (by file name, since otr moves them
+    /// from the working directory to its sub directory), with their state and
+    /// the point in time since when they have that state
+    files: HashMap<OsString, (FileState, Instant)>,
+    /// Stable encoded video files that existed when the videos were processed
+    /// last
+    processed: HashSet<OsString>,
+    /// Point in time when the videos were processed last
+    last_run: Option<Instant>,
+}
+
+impl Watcher {
+    pub fn new(debounce: Duration, retry_interval: Duration) -> Self {
+        Watcher {
+            debounce,
+            retry_interval,
+            files: HashMap::new(),
+            processed: HashSet::new(),
+            last_run: None,
+        }
+    }
+
+    /// Blocks until the videos are to be processed. That's the case initially,
+    /// if new encoded video files became stable (i.e., did not change for the
+    /// debounce time), if the retry interval elapsed since the last run, or if
+    /// is_woken returns true (e.g., since videos were deferred and can be
+    /// processed now). Encoded video files that are still changing cannot be
+    /// decoded yet. Their names are returned, so that they can be skipped, while
+    /// all other videos are processed
+    pub fn wait<F>(&mut self, is_woken: F) -> HashSet<OsString>
+    where
+        F: Fn() -> bool,
+    {
+        loop {
+            if let Err(err) = self.scan() {
+                warn!("{:?}", err);
+            } else {
+                let now = Instant::now();
+                let (stable, changing): (HashSet<OsString>, HashSet<OsString>) =
+                    self.files.iter().partition_map(|(file_name, (_, since))| {
+                        if now.duration_since(*since) >= self.debounce {
+                            Either::Left(file_name.clone())
+                        } else {
+                            Either::Right(file_name.clone())
+                        }
+                    });
+                let is_due = match self.last_run {
+                    None => true,
+                    Some(last_run) => {
+                        now.duration_since(last_run) >= self.retry_interval
+                            || is_woken()
+                            || stable
+                                .iter()
+                                .any(|file_name| !self.processed.contains(file_name))
+                    }
+                };
+
+                if is_due {
+                    self.processed = stable;
+                    self.last_run = Some(now);
+                    return changing;
+                }
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Determines the current state of the encoded video files in the working
+    /// directory and its sub directory for encoded videos
+    fn scan(&mut self) -> anyhow::Result<()> {
+        let mut files: HashMap<OsString, (FileState, Instant)> = HashMap::new();
+        let open_for_writing = collecting::files_open_for_writing();
+
+        for dir_kind in [DirKind::Root, DirKind::Encoded] {
+            let dir = dirs::working_sub_dir(&dir_kind)?;
+            for entry in fs::read_dir(dir)
+                .with_context(|| format!("Could not read directory \"{}\"", dir.display()))?
+            {
+                let path = entry?.path();
+                if !path.is_file() {
+                    continue;
+                }
+                let video = match Video::new(&path) {
+                    Ok(_video) if _video.status() == Status::Encoded => _video,
+                    _ => continue,
+                };
+
+                let metadata = match fs::metadata(&path) {
+                    Ok(_metadata) => _metadata,
+                    // File might have been moved in the meantime
+                    Err(_) => continue,
+                };
+                let state = FileState {
+                    len: metadata.len(),
+                    modified: metadata.modified()?,
+                };
+
+                // Keep the point in time since when the file has its state if
+                // it did not change and is not being written
+                let file_name = path.file_name().unwrap().to_os_string();
+                let since = match self.files.remove(&file_name) {
+                    Some((_state, _since))
+                        if _state == state && !video.is_being_written(&open_for_writing) =>
+                    {
+                        _since
+                    }
+                    _ => {
+                        trace!("Encoded video \"{}\" changed", path.display());
+                        Instant::now()
+                    }
+                };
+                files.insert(file_name, (state, since));
+            }
+        }
+
+        self.files = files;
+
+        Ok(())
+    }
+}